@@ -17,7 +17,6 @@ fn main() -> Result<(), CompileError> {
     let slint_files: Vec<_> = ui_dir_path
         .read_dir()
         .unwrap()
-        .into_iter()
         .filter_map(|path_info| {
             let path = path_info.unwrap().path();
             if path.is_file() && path.to_string_lossy().ends_with(".slint") {
@@ -1,53 +1,180 @@
 // Prevent console window in addition to Slint window in Windows release builds when, e.g., starting the app via file manager. Ignored on other platforms.
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::{
+    cell::{Cell, RefCell},
+    env, fs,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use slint::{
+    ComponentHandle, Model, ModelRc, PhysicalPosition, PhysicalSize, SharedString, Timer,
+    TimerMode, VecModel,
+};
+
 use modern_minesweeper::controller::{
-    AboutDialog, GameConfig, GameDifficulty, GameState, MINE_VALUE, MainWindow, StateDialog,
-    change_flag, change_visibility, check_win, clear_grid, expand_selection, fill_grid, new_grid,
-    vec2d_to_model_grid, zero_pad,
+    AboutDialog, Appearance, ColorPalette, FirstClick, GameConfig, GameDifficulty, GameMode,
+    GameState, GameSummary, HighScoresDialog, MINE_VALUE, MainWindow, Neighbourhood,
+    SettingsDialog, Shape, StateDialog, StatisticsDialog, Theme, Tile, Topology, board_state,
+    board_text::{export_board, parse_board},
+    change_flag, change_visibility, check_win, clear_grid,
+    clock::{GameClock, is_time_up, time_left},
+    endless::EndlessBoard,
+    expand_selection, format_time,
+    high_scores::{HighScore, HighScores},
+    layer_rows, lives_left,
+    mask::parse_mask,
+    max_neighbours,
+    metrics::GameMetrics,
+    mine_hit, new_grid, place_mines, remaining_flags,
+    replay::{Replay, ReplayAction, apply_event},
+    rmv::{read_rmv, write_rmv},
+    settings::{BoardZoom, Settings, WindowGeometry},
+    skin::{SkinError, load_user_skin, user_skins},
+    solver::{Deduction, hint},
+    statistics::{Statistics, config_name},
+    tile_at, vec2d_to_model_grid, zero_pad,
 };
-use slint::ComponentHandle;
-use std::{cell::RefCell, env, rc::Rc};
 
 fn main() -> Result<(), slint::PlatformError> {
     unsafe {
         env::set_var("RUST_BACKTRACE", "1");
     }
 
-    // Nullptr to State Dialog and About Dialog
+    // Nullptr to State Dialog, About Dialog, Statistics Dialog, High Scores Dialog and Settings Dialog
     let state_dialog = Rc::new(RefCell::new(Option::<StateDialog>::None));
     let about_dialog = Rc::new(RefCell::new(Option::<AboutDialog>::None));
+    let statistics_dialog = Rc::new(RefCell::new(Option::<StatisticsDialog>::None));
+    let high_scores_dialog = Rc::new(RefCell::new(Option::<HighScoresDialog>::None));
+    let settings_dialog = Rc::new(RefCell::new(Option::<SettingsDialog>::None));
+    let dialogs = Dialogs {
+        state: state_dialog.clone(),
+        about: about_dialog.clone(),
+        statistics: statistics_dialog.clone(),
+        high_scores: high_scores_dialog.clone(),
+        settings: settings_dialog.clone(),
+    };
+
+    // Preferences of the player
+    let settings = Rc::new(RefCell::new(Settings::load()));
 
-    // Global Configs
-    let level = Rc::new(RefCell::new(GameDifficulty::Medium));
-    let game_config = Rc::new(RefCell::new(GameConfig::new(*level.borrow())));
+    // Statistics of finished games
+    let statistics = Rc::new(RefCell::new(Statistics::load()));
+
+    // High scores and the one waiting for a name
+    let high_scores = Rc::new(RefCell::new(HighScores::load()));
+    let pending_score = Rc::new(RefCell::new(Option::<(GameConfig, HighScore)>::None));
+
+    // Global Configs, the last difficulty played
+    let game_config = Rc::new(RefCell::new(settings.borrow().game_config()));
 
     // Empty Grid
-    let tiles = Rc::new(RefCell::new(new_grid(&*game_config.borrow())));
-    let model = vec2d_to_model_grid(&*tiles.borrow());
-    let text_font_size = 28.0;
+    let tiles = Rc::new(RefCell::new(new_grid(&game_config.borrow())));
+    // Board opened from a file, restart goes back to it instead of a random board
+    let loaded_board = Rc::new(RefCell::new(Option::<Vec<Vec<Tile>>>::None));
+
+    // Replay of the last game and the timer playing a watched replay
+    let replay = Rc::new(RefCell::new(Option::<Replay>::None));
+    let playback_timer = Rc::new(Timer::default());
+
+    // Game time, kept by Rust and shown to hundredths
+    let clock = Rc::new(RefCell::new(GameClock::default()));
+    let clock_timer = Timer::default();
+    // Boards cleared in the running time attack and the time its current board was started at
+    let boards_cleared = Rc::new(Cell::new(0));
+    let board_started = Rc::new(Cell::new(Duration::ZERO));
+    // Endless board once its first tile is revealed, and its tile shown at the top left of the view
+    let endless_board = Rc::new(RefCell::new(Option::<EndlessBoard>::None));
+    let view_origin = Rc::new(Cell::new((0, 0)));
     let main_window = MainWindow::new()?;
-    main_window.set_grid(model);
     main_window.set_state(GameState::Initial);
     main_window.set_mine_value(MINE_VALUE);
     main_window.set_flags(game_config.borrow().mine_count as i32);
-    main_window.set_text_font_size(text_font_size);
-    main_window.set_levels(GameDifficulty::create_model());
-    main_window.invoke_initial_level((*level.borrow()).into());
+    main_window.set_text_font_size(settings.borrow().font_size);
+    main_window.set_key_bindings(settings.borrow().key_bindings.clone());
+    main_window.set_levels(levels_model());
+    main_window.set_neighbourhoods(Neighbourhood::create_model());
+    main_window.set_shapes(Shape::create_model());
+    main_window.set_modes(GameMode::create_model());
+    main_window.invoke_initial_level(level_index(&settings.borrow()));
     main_window.on_zero_pad(|number, length| zero_pad(number, length).into());
+    let theme = main_window.global::<Theme>();
+    theme.set_palette(settings.borrow().palette);
+    theme.set_appearance(settings.borrow().appearance);
+    main_window.set_skins(skin_names(&main_window));
+    apply_zoom(&main_window, &settings.borrow(), &game_config.borrow());
+    apply_topology(&main_window, &game_config.borrow());
+    show_board(&main_window, &game_config.borrow(), &tiles.borrow());
+    if let Err(err) = apply_skin(&main_window, &settings.borrow().skin) {
+        eprintln!("Failed to load skin {}: {err}", settings.borrow().skin);
+    }
+    if settings.borrow().remember_window
+        && let Some(geometry) = settings.borrow().window
+    {
+        let window = main_window.window();
+        window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+        window.set_maximized(geometry.maximized);
+    }
+
+    // Time Display, timed modes end when their limit runs out
+    let main_window_weak = main_window.as_weak();
+    let clock_cloned = clock.clone();
+    let game_config_cloned = game_config.clone();
+    clock_timer.start(TimerMode::Repeated, Duration::from_millis(30), move || {
+        let main_window = main_window_weak.unwrap();
+        if !clock_cloned.borrow().is_running() {
+            return;
+        }
+        if main_window.window().is_minimized() {
+            main_window.invoke_pause(true);
+        }
+        let elapsed = clock_cloned.borrow().elapsed();
+        if is_time_up(&game_config_cloned.borrow(), elapsed) {
+            main_window.invoke_time_up();
+            return;
+        }
+        show_time(&main_window, &game_config_cloned.borrow(), elapsed);
+    });
 
     // First Move Occured
     let main_window_weak = main_window.as_weak();
     let game_config_cloned = game_config.clone();
     let tiles_cloned = tiles.clone();
+    let loaded_board_cloned = loaded_board.clone();
+    let replay_cloned = replay.clone();
+    let clock_cloned = clock.clone();
+    let settings_cloned = settings.clone();
+    let board_started_cloned = board_started.clone();
     main_window.on_first_move_occured(move |position| {
-        fill_grid(
-            &*game_config_cloned.borrow(),
-            position,
-            &mut *tiles_cloned.borrow_mut(),
+        // Loaded boards already have their mines
+        if loaded_board_cloned.borrow().is_none() {
+            place_mines(
+                &game_config_cloned.borrow(),
+                &position,
+                settings_cloned.borrow().first_click,
+                &mut tiles_cloned.borrow_mut(),
+            );
+        }
+        replay_cloned.replace(Some(Replay::new(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        )));
+        // A time attack keeps its clock running from one board to the next
+        if clock_cloned.borrow().is_running() {
+            board_started_cloned.set(clock_cloned.borrow().elapsed());
+        } else {
+            board_started_cloned.set(Duration::ZERO);
+            clock_cloned.borrow_mut().start();
+        }
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
         );
-        let model = vec2d_to_model_grid(&*tiles_cloned.borrow());
-        main_window_weak.unwrap().set_grid(model);
         main_window_weak.unwrap().set_state(GameState::Normal);
     });
 
@@ -65,22 +192,63 @@ fn main() -> Result<(), slint::PlatformError> {
     let tiles_cloned = tiles.clone();
     let game_config_cloned = game_config.clone();
     let state_dialog_cloned = state_dialog.clone();
+    let loaded_board_cloned = loaded_board.clone();
+    let playback_timer_cloned = playback_timer.clone();
+    let clock_cloned = clock.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let endless_board_cloned = endless_board.clone();
+    let view_origin_cloned = view_origin.clone();
     main_window.on_restart(move || {
-        clear_grid(&mut *tiles_cloned.borrow_mut());
-        let model = vec2d_to_model_grid(&*tiles_cloned.borrow());
-        main_window_weak.unwrap().set_grid(model);
-        main_window_weak.unwrap().set_state(GameState::Initial);
-        main_window_weak
-            .unwrap()
-            .set_flags(game_config_cloned.borrow().mine_count as i32);
-        main_window_weak.unwrap().invoke_reset_timer();
-        // Close State Dialog if present
-        if state_dialog_cloned.borrow().is_some() {
-            let state_dialog = state_dialog_cloned.borrow();
-            let state_dialog = state_dialog.as_ref().unwrap();
-            state_dialog
-                .window()
-                .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+        playback_timer_cloned.stop();
+        main_window_weak.unwrap().set_replaying(false);
+        match &*loaded_board_cloned.borrow() {
+            Some(board) => tiles_cloned.borrow_mut().clone_from(board),
+            None => clear_grid(&mut tiles_cloned.borrow_mut()),
+        }
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        );
+        main_window_weak.unwrap().set_state(board_state(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        ));
+        main_window_weak.unwrap().set_flags(remaining_flags(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        ));
+        reset_clock(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &clock_cloned,
+        );
+        boards_cleared_cloned.set(0);
+        main_window_weak.unwrap().set_boards_cleared(0);
+        endless_board_cloned.replace(None);
+        view_origin_cloned.set((0, 0));
+        main_window_weak.unwrap().set_score(0);
+        close_state_dialog(state_dialog_cloned.clone());
+    });
+
+    // Pause Button
+    let main_window_weak = main_window.as_weak();
+    let clock_cloned = clock.clone();
+    main_window.on_pause(move |paused| {
+        let main_window = main_window_weak.unwrap();
+        if main_window.get_replaying() {
+            return;
+        }
+        match (paused, main_window.get_state()) {
+            (true, GameState::Normal) => {
+                clock_cloned.borrow_mut().pause();
+                main_window.set_state(GameState::Paused);
+            }
+            (false, GameState::Paused) => {
+                clock_cloned.borrow_mut().resume();
+                main_window.set_state(GameState::Normal);
+            }
+            _ => {}
         }
     });
 
@@ -89,46 +257,155 @@ fn main() -> Result<(), slint::PlatformError> {
     let game_config_cloned = game_config.clone();
     let tiles_cloned = tiles.clone();
     let state_dialog_cloned = state_dialog.clone();
+    let statistics_cloned = statistics.clone();
+    let clock_cloned = clock.clone();
+    let high_scores_cloned = high_scores.clone();
+    let pending_score_cloned = pending_score.clone();
+    let replay_cloned = replay.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let board_started_cloned = board_started.clone();
     main_window.on_expand_selection(move |position| {
-        if let Some(_lose) = expand_selection(
-            &*game_config_cloned.borrow(),
+        let lost = expand_selection(
+            &game_config_cloned.borrow(),
             &position,
-            &mut *tiles_cloned.borrow_mut(),
-        ) {
+            &mut tiles_cloned.borrow_mut(),
+        );
+        if let Some(_lose) = lost {
             main_window_weak.unwrap().set_state(GameState::Lose);
-            // State Dialog
-            create_state_dialog(state_dialog_cloned.clone(), text_font_size);
-            let state_dialog = state_dialog_cloned.borrow();
-            let state_dialog = state_dialog.as_ref().unwrap();
-            state_dialog.set_state(GameState::Lose);
-            state_dialog.show().unwrap();
+            let time = stop_clock(
+                &main_window_weak.unwrap(),
+                &game_config_cloned.borrow(),
+                &clock_cloned,
+            );
+            // Every board of a time attack counts as a game of its own
+            record_game(
+                &statistics_cloned,
+                &game_config_cloned.borrow(),
+                &tiles_cloned.borrow(),
+                &replay_cloned,
+                false,
+                time.saturating_sub(board_started_cloned.get()),
+            );
+            show_result_dialog(
+                &main_window_weak.unwrap(),
+                state_dialog_cloned.clone(),
+                high_scores_cloned.clone(),
+                pending_score_cloned.clone(),
+                replay_cloned.clone(),
+                GameState::Lose,
+                GameSummary::default(),
+                results_text(
+                    &game_config_cloned.borrow(),
+                    &tiles_cloned.borrow(),
+                    boards_cleared_cloned.get(),
+                ),
+            );
         }
-        let model = vec2d_to_model_grid(&*tiles_cloned.borrow());
-        main_window_weak.unwrap().set_grid(model);
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        );
+        main_window_weak.unwrap().set_flags(remaining_flags(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        ));
     });
 
     // Change Flag
+    let game_config_cloned = game_config.clone();
     let tiles_cloned = tiles.clone();
-    main_window.on_change_flag(move |position, flag| {
-        change_flag(&mut *tiles_cloned.borrow_mut(), &position, flag);
+    let replay_cloned = replay.clone();
+    let clock_cloned = clock.clone();
+    let main_window_weak = main_window.as_weak();
+    main_window.on_change_flag(move |position, flags| {
+        change_flag(
+            &game_config_cloned.borrow(),
+            &mut tiles_cloned.borrow_mut(),
+            &position,
+            flags,
+        );
+        if main_window_weak.unwrap().get_state() == GameState::Normal
+            && let Some(replay) = &mut *replay_cloned.borrow_mut()
+        {
+            // Flags are only added one at a time or all taken off
+            let action = if flags > 0 {
+                ReplayAction::Flag
+            } else {
+                ReplayAction::Unflag
+            };
+            replay.record(clock_cloned.borrow().elapsed(), action, position);
+        }
     });
 
     // Change Visibility
+    let game_config_cloned = game_config.clone();
     let tiles_cloned = tiles.clone();
     let main_window_weak = main_window.as_weak();
     let state_dialog_cloned = state_dialog.clone();
+    let replay_cloned = replay.clone();
+    let clock_cloned = clock.clone();
+    let statistics_cloned = statistics.clone();
+    let high_scores_cloned = high_scores.clone();
+    let pending_score_cloned = pending_score.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let board_started_cloned = board_started.clone();
     main_window.on_change_visibility(move |position, visible| {
-        change_visibility(&mut *tiles_cloned.borrow_mut(), &position, visible);
+        // Clicking an already visible number chords around it
+        let was_visible =
+            tile_at(&game_config_cloned.borrow(), &tiles_cloned.borrow(), &position).visible;
+        if let Some(replay) = &mut *replay_cloned.borrow_mut() {
+            let action = if was_visible {
+                ReplayAction::Chord
+            } else {
+                ReplayAction::Reveal
+            };
+            replay.record(
+                clock_cloned.borrow().elapsed(),
+                action,
+                position.clone(),
+            );
+        }
+        change_visibility(
+            &game_config_cloned.borrow(),
+            &mut tiles_cloned.borrow_mut(),
+            &position,
+            visible,
+        );
+        // A mine costs a life when revealed, the game goes on while some are left
+        let game_config = &*game_config_cloned.borrow();
+        if was_visible
+            || tile_at(game_config, &tiles_cloned.borrow(), &position).value != MINE_VALUE
+        {
+            return;
+        }
+        let lost = mine_hit(game_config, &mut tiles_cloned.borrow_mut(), &position);
         let tiles_ref = &*tiles_cloned.borrow();
-        let tile = &tiles_ref[position.row as usize][position.col as usize];
-        if tile.value == MINE_VALUE {
+        show_board(&main_window_weak.unwrap(), game_config, tiles_ref);
+        main_window_weak
+            .unwrap()
+            .set_flags(remaining_flags(game_config, tiles_ref));
+        if lost.is_some() {
             main_window_weak.unwrap().set_state(GameState::Lose);
-            // State Dialog
-            create_state_dialog(state_dialog_cloned.clone(), text_font_size);
-            let state_dialog = state_dialog_cloned.borrow();
-            let state_dialog = state_dialog.as_ref().unwrap();
-            state_dialog.set_state(GameState::Lose);
-            state_dialog.show().unwrap();
+            let time = stop_clock(&main_window_weak.unwrap(), game_config, &clock_cloned);
+            record_game(
+                &statistics_cloned,
+                game_config,
+                tiles_ref,
+                &replay_cloned,
+                false,
+                time.saturating_sub(board_started_cloned.get()),
+            );
+            show_result_dialog(
+                &main_window_weak.unwrap(),
+                state_dialog_cloned.clone(),
+                high_scores_cloned.clone(),
+                pending_score_cloned.clone(),
+                replay_cloned.clone(),
+                GameState::Lose,
+                GameSummary::default(),
+                results_text(game_config, tiles_ref, boards_cleared_cloned.get()),
+            );
         }
     });
 
@@ -137,14 +414,80 @@ fn main() -> Result<(), slint::PlatformError> {
     let tiles_cloned = tiles.clone();
     let main_window_weak = main_window.as_weak();
     let state_dialog_cloned = state_dialog.clone();
+    let statistics_cloned = statistics.clone();
+    let clock_cloned = clock.clone();
+    let high_scores_cloned = high_scores.clone();
+    let pending_score_cloned = pending_score.clone();
+    let replay_cloned = replay.clone();
+    let loaded_board_cloned = loaded_board.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let board_started_cloned = board_started.clone();
     main_window.on_check_win(move || {
-        if check_win(&*game_config_cloned.borrow(), &*tiles_cloned.borrow()) {
+        if main_window_weak.unwrap().get_state() == GameState::Normal
+            && check_win(&game_config_cloned.borrow(), &tiles_cloned.borrow())
+        {
+            // A time attack deals the next board straight away and keeps the clock running
+            if game_config_cloned.borrow().mode == GameMode::TimeAttack {
+                let game_config = &*game_config_cloned.borrow();
+                let time = clock_cloned.borrow().elapsed();
+                record_game(
+                    &statistics_cloned,
+                    game_config,
+                    &tiles_cloned.borrow(),
+                    &replay_cloned,
+                    true,
+                    time.saturating_sub(board_started_cloned.get()),
+                );
+                boards_cleared_cloned.set(boards_cleared_cloned.get() + 1);
+                loaded_board_cloned.replace(None);
+                tiles_cloned.borrow_mut().clone_from(&new_grid(game_config));
+                let main_window = main_window_weak.unwrap();
+                main_window.set_boards_cleared(boards_cleared_cloned.get() as i32);
+                show_board(&main_window, game_config, &tiles_cloned.borrow());
+                main_window.set_flags(game_config.mine_count as i32);
+                main_window.set_state(GameState::Initial);
+                return;
+            }
             main_window_weak.unwrap().set_state(GameState::Win);
-            create_state_dialog(state_dialog_cloned.clone(), text_font_size);
-            let state_dialog = state_dialog_cloned.borrow();
-            let state_dialog = state_dialog.as_ref().unwrap();
-            state_dialog.set_state(GameState::Win);
-            state_dialog.show().unwrap();
+            let time = stop_clock(
+                &main_window_weak.unwrap(),
+                &game_config_cloned.borrow(),
+                &clock_cloned,
+            );
+            let metrics = record_game(
+                &statistics_cloned,
+                &game_config_cloned.borrow(),
+                &tiles_cloned.borrow(),
+                &replay_cloned,
+                true,
+                time,
+            );
+            // High Score
+            let game_config = &*game_config_cloned.borrow();
+            pending_score_cloned.replace(None);
+            if high_scores_cloned
+                .borrow()
+                .qualifies(game_config, metrics.score_time())
+            {
+                let high_score = HighScore {
+                    name: String::new(),
+                    time_ms: metrics.score_time().as_millis() as u64,
+                    date: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+                    three_bv: metrics.three_bv,
+                    efficiency: metrics.ioe(),
+                };
+                pending_score_cloned.replace(Some((game_config.clone(), high_score)));
+            }
+            show_result_dialog(
+                &main_window_weak.unwrap(),
+                state_dialog_cloned.clone(),
+                high_scores_cloned.clone(),
+                pending_score_cloned.clone(),
+                replay_cloned.clone(),
+                GameState::Win,
+                metrics.summary(),
+                String::new(),
+            );
         }
     });
 
@@ -152,52 +495,781 @@ fn main() -> Result<(), slint::PlatformError> {
     let game_config_cloned = game_config.clone();
     let tiles_cloned = tiles.clone();
     let main_window_weak = main_window.as_weak();
+    let loaded_board_cloned = loaded_board.clone();
+    let settings_cloned = settings.clone();
+    let clock_cloned = clock.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let endless_board_cloned = endless_board.clone();
+    let view_origin_cloned = view_origin.clone();
     main_window.on_level_changed(move |index| {
-        level.borrow_mut().clone_from(&index.into());
-        game_config_cloned
-            .borrow_mut()
-            .clone_from(&GameConfig::new(*level.borrow()));
+        loaded_board_cloned.replace(None);
+        {
+            let settings = &mut *settings_cloned.borrow_mut();
+            // The entry after the difficulties is the custom board
+            settings.custom_difficulty = index == custom_level_index();
+            if !settings.custom_difficulty {
+                settings.difficulty = index.into();
+            }
+            game_config_cloned.replace(settings.game_config());
+            if let Err(err) = settings.save() {
+                eprintln!("Failed to save settings: {err}");
+            }
+        }
         tiles_cloned
             .borrow_mut()
-            .clone_from(&new_grid(&*game_config_cloned.borrow()));
-        let model = vec2d_to_model_grid(&*tiles_cloned.borrow());
-        main_window_weak.unwrap().set_grid(model);
+            .clone_from(&new_grid(&game_config_cloned.borrow()));
+        apply_topology(&main_window_weak.unwrap(), &game_config_cloned.borrow());
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        );
         main_window_weak
             .unwrap()
             .set_flags(game_config_cloned.borrow().mine_count as i32);
+        // Ends a time attack between two of its boards
+        reset_clock(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &clock_cloned,
+        );
+        boards_cleared_cloned.set(0);
+        main_window_weak.unwrap().set_boards_cleared(0);
+        endless_board_cloned.replace(None);
+        view_origin_cloned.set((0, 0));
+        main_window_weak.unwrap().set_score(0);
+        apply_zoom(
+            &main_window_weak.unwrap(),
+            &settings_cloned.borrow(),
+            &game_config_cloned.borrow(),
+        );
+    });
+
+    // Topology Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_topology_changed(move |index| {
+        settings_cloned.borrow_mut().topology = index.into();
+        // Changing the level saves the settings and makes the new board
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Layers Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_layers_changed(move |layer_count| {
+        settings_cloned.borrow_mut().layer_count = layer_count.max(1) as usize;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Layer Changed
+    let main_window_weak = main_window.as_weak();
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    main_window.on_layer_changed(move |_layer| {
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        );
+    });
+
+    // Neighbourhood Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_neighbourhood_changed(move |index| {
+        settings_cloned.borrow_mut().neighbourhood = index.into();
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Mines per Tile Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_tile_mines_changed(move |max_tile_mines| {
+        settings_cloned.borrow_mut().max_tile_mines = max_tile_mines.max(1) as usize;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Liar Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_liar_changed(move |liar| {
+        settings_cloned.borrow_mut().liar = liar;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Lives Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_lives_changed(move |lives| {
+        settings_cloned.borrow_mut().lives = lives.max(1) as usize;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Mode Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_mode_changed(move |index| {
+        settings_cloned.borrow_mut().mode = index.into();
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Time Limit Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_time_limit_changed(move |secs| {
+        settings_cloned.borrow_mut().time_limit_secs = secs.max(1) as u64;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Time Up, the limit of a timed mode ran out before the board was cleared
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    let main_window_weak = main_window.as_weak();
+    let state_dialog_cloned = state_dialog.clone();
+    let statistics_cloned = statistics.clone();
+    let clock_cloned = clock.clone();
+    let high_scores_cloned = high_scores.clone();
+    let pending_score_cloned = pending_score.clone();
+    let replay_cloned = replay.clone();
+    let boards_cleared_cloned = boards_cleared.clone();
+    let board_started_cloned = board_started.clone();
+    main_window.on_time_up(move || {
+        let main_window = main_window_weak.unwrap();
+        let game_config = &*game_config_cloned.borrow();
+        let tiles = &*tiles_cloned.borrow();
+        // A time attack can run out between two boards, before the next one is started
+        let playing = main_window.get_state() == GameState::Normal;
+        main_window.set_state(GameState::TimeUp);
+        let time = stop_clock(&main_window, game_config, &clock_cloned);
+        if playing {
+            record_game(
+                &statistics_cloned,
+                game_config,
+                tiles,
+                &replay_cloned,
+                false,
+                time.saturating_sub(board_started_cloned.get()),
+            );
+        }
+        show_result_dialog(
+            &main_window,
+            state_dialog_cloned.clone(),
+            high_scores_cloned.clone(),
+            pending_score_cloned.clone(),
+            replay_cloned.clone(),
+            GameState::TimeUp,
+            GameSummary::default(),
+            results_text(game_config, tiles, boards_cleared_cloned.get()),
+        );
+    });
+
+    // Endless Click, the first reveal makes the board and keeps the tiles around it free of mines
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    let main_window_weak = main_window.as_weak();
+    let state_dialog_cloned = state_dialog.clone();
+    let clock_cloned = clock.clone();
+    let high_scores_cloned = high_scores.clone();
+    let pending_score_cloned = pending_score.clone();
+    let replay_cloned = replay.clone();
+    let statistics_cloned = statistics.clone();
+    let endless_board_cloned = endless_board.clone();
+    let view_origin_cloned = view_origin.clone();
+    main_window.on_endless_click(move |position, flag| {
+        let main_window = main_window_weak.unwrap();
+        let game_config = &*game_config_cloned.borrow();
+        let (top, left) = view_origin_cloned.get();
+        let (row, col) = (top + position.row as i64, left + position.col as i64);
+        let mut endless_board = endless_board_cloned.borrow_mut();
+        if endless_board.is_none() {
+            if flag {
+                return;
+            }
+            let density = EndlessBoard::density(game_config);
+            endless_board.replace(EndlessBoard::new(rand::random(), density, (row, col)));
+            replay_cloned.replace(None);
+            clock_cloned.borrow_mut().start();
+            main_window.set_state(GameState::Normal);
+        }
+        let board = endless_board.as_mut().unwrap();
+        let lost = if flag {
+            board.toggle_flag(row, col);
+            None
+        } else if board.tile(row, col).visible {
+            board.chord(row, col)
+        } else {
+            board.reveal(row, col)
+        };
+        show_endless(
+            &main_window,
+            game_config,
+            board,
+            (top, left),
+            &mut tiles_cloned.borrow_mut(),
+        );
+        if lost.is_some() {
+            main_window.set_state(GameState::Lose);
+            let time = stop_clock(&main_window, game_config, &clock_cloned);
+            // An endless board is never cleared, every game ends as a loss
+            record_game(
+                &statistics_cloned,
+                game_config,
+                &tiles_cloned.borrow(),
+                &replay_cloned,
+                false,
+                time,
+            );
+            show_result_dialog(
+                &main_window,
+                state_dialog_cloned.clone(),
+                high_scores_cloned.clone(),
+                pending_score_cloned.clone(),
+                replay_cloned.clone(),
+                GameState::Lose,
+                GameSummary::default(),
+                results_text(game_config, &tiles_cloned.borrow(), board.score()),
+            );
+        }
+    });
+
+    // Scroll View of the endless board
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    let main_window_weak = main_window.as_weak();
+    let endless_board_cloned = endless_board.clone();
+    let view_origin_cloned = view_origin.clone();
+    main_window.on_scroll_view(move |rows, cols| {
+        if rows == 0 && cols == 0 {
+            return;
+        }
+        let (top, left) = view_origin_cloned.get();
+        let origin = (top + rows as i64, left + cols as i64);
+        view_origin_cloned.set(origin);
+        // The hidden view of a board not made yet looks the same anywhere
+        if let Some(board) = &mut *endless_board_cloned.borrow_mut() {
+            show_endless(
+                &main_window_weak.unwrap(),
+                &game_config_cloned.borrow(),
+                board,
+                origin,
+                &mut tiles_cloned.borrow_mut(),
+            );
+        }
+    });
+
+    // Shape Changed, a preset replaces the mask opened from a file
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_shape_changed(move |index| {
+        let edited = Settings {
+            shape: index.into(),
+            mask: None,
+            ..settings_cloned.borrow().clone()
+        };
+        if let Err(err) = edited.validate() {
+            show_error("Shape", &err.to_string());
+            return;
+        }
+        settings_cloned.replace(edited);
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Open Shape
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_open_shape(move || {
+        let Some(path) = FileDialog::new()
+            .set_title("Open Shape")
+            .add_filter("Shape", &["txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        let name = path
+            .file_stem()
+            .map_or("Shape".into(), |stem| stem.to_string_lossy());
+        let mask = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| parse_mask(&name, &text).map_err(|err| err.to_string()));
+        let edited = mask.and_then(|mask| {
+            let edited = Settings {
+                mask: Some(mask),
+                ..settings_cloned.borrow().clone()
+            };
+            edited.validate().map_err(|err| err.to_string())?;
+            Ok(edited)
+        });
+        match edited {
+            Ok(edited) => settings_cloned.replace(edited),
+            Err(err) => {
+                show_error("Open Shape", &err);
+                return;
+            }
+        };
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Hint
+    let main_window_weak = main_window.as_weak();
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    main_window.on_hint(move || {
+        match hint(&game_config_cloned.borrow(), &tiles_cloned.borrow()) {
+            Some(hint) => main_window_weak
+                .unwrap()
+                .invoke_show_hint(hint.position, hint.deduction == Deduction::Mine),
+            None => {
+                MessageDialog::new()
+                    .set_level(MessageLevel::Info)
+                    .set_title("Hint")
+                    .set_description("No tile is certain, the next move is a guess")
+                    .show();
+            }
+        }
+    });
+
+    // Wrap Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_wrap_changed(move |wrap| {
+        settings_cloned.borrow_mut().wrap = wrap;
+        let level = level_index(&settings_cloned.borrow());
+        main_window_weak.unwrap().invoke_level_changed(level);
+    });
+
+    // Open Board
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    let main_window_weak = main_window.as_weak();
+    let state_dialog_cloned = state_dialog.clone();
+    let loaded_board_cloned = loaded_board.clone();
+    let playback_timer_cloned = playback_timer.clone();
+    let clock_cloned = clock.clone();
+    let settings_cloned = settings.clone();
+    main_window.on_open_board(move || {
+        let Some(path) = FileDialog::new()
+            .set_title("Open Board")
+            .add_filter("Board", &["txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        let board = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| parse_board(&text).map_err(|err| err.to_string()));
+        let (config, board) = match board {
+            Ok(board) => board,
+            Err(err) => {
+                show_error("Open Board", &err);
+                return;
+            }
+        };
+        playback_timer_cloned.stop();
+        main_window_weak.unwrap().set_replaying(false);
+        game_config_cloned.replace(config);
+        tiles_cloned.borrow_mut().clone_from(&board);
+        loaded_board_cloned.replace(Some(board));
+        apply_topology(&main_window_weak.unwrap(), &game_config_cloned.borrow());
+        show_board(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        );
+        main_window_weak.unwrap().set_state(board_state(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        ));
+        main_window_weak.unwrap().set_flags(remaining_flags(
+            &game_config_cloned.borrow(),
+            &tiles_cloned.borrow(),
+        ));
+        reset_clock(
+            &main_window_weak.unwrap(),
+            &game_config_cloned.borrow(),
+            &clock_cloned,
+        );
+        apply_zoom(
+            &main_window_weak.unwrap(),
+            &settings_cloned.borrow(),
+            &game_config_cloned.borrow(),
+        );
+        close_state_dialog(state_dialog_cloned.clone());
+    });
+
+    // Export Board
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    main_window.on_export_board(move || {
+        if !game_config_cloned.borrow().is_classic() {
+            show_error("Export Board", "Board files hold only flat square boards");
+            return;
+        }
+        let text = match export_board(&tiles_cloned.borrow()) {
+            Ok(text) => text,
+            Err(err) => {
+                show_error("Export Board", &err.to_string());
+                return;
+            }
+        };
+        let Some(path) = FileDialog::new()
+            .set_title("Export Board")
+            .add_filter("Board", &["txt"])
+            .set_file_name("board.txt")
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(err) = fs::write(&path, text) {
+            show_error("Export Board", &err.to_string());
+        }
+    });
+
+    // Watch Replay
+    let game_config_cloned = game_config.clone();
+    let tiles_cloned = tiles.clone();
+    let main_window_weak = main_window.as_weak();
+    let state_dialog_cloned = state_dialog.clone();
+    let loaded_board_cloned = loaded_board.clone();
+    let replay_cloned = replay.clone();
+    let playback_timer_cloned = playback_timer.clone();
+    let clock_cloned = clock.clone();
+    let settings_cloned = settings.clone();
+    main_window.on_watch_replay(move || {
+        let Some(path) = FileDialog::new()
+            .set_title("Watch Replay")
+            .add_filter("Minesweeper Clone Replay", &["rmv"])
+            .pick_file()
+        else {
+            return;
+        };
+        let watched = fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| read_rmv(&data).map_err(|err| err.to_string()));
+        let watched = match watched {
+            Ok(watched) => watched,
+            Err(err) => {
+                show_error("Watch Replay", &err);
+                return;
+            }
+        };
+        playback_timer_cloned.stop();
+        close_state_dialog(state_dialog_cloned.clone());
+        loaded_board_cloned.replace(None);
+        game_config_cloned.replace(watched.game_config.clone());
+        tiles_cloned.borrow_mut().clone_from(&watched.board);
+        let main_window = main_window_weak.unwrap();
+        apply_topology(&main_window, &watched.game_config);
+        show_board(&main_window, &watched.game_config, &tiles_cloned.borrow());
+        main_window.set_state(GameState::Normal);
+        main_window.set_flags(remaining_flags(
+            &watched.game_config,
+            &tiles_cloned.borrow(),
+        ));
+        reset_clock(&main_window, &watched.game_config, &clock_cloned);
+        apply_zoom(&main_window, &settings_cloned.borrow(), &watched.game_config);
+        main_window.set_replaying(true);
+
+        let events = watched.events.clone();
+        replay_cloned.replace(Some(watched));
+        let next_event = Cell::new(0);
+        let started = Instant::now();
+        let game_config_cloned = game_config_cloned.clone();
+        let tiles_cloned = tiles_cloned.clone();
+        let main_window_weak = main_window_weak.clone();
+        let playback_timer_weak = Rc::downgrade(&playback_timer_cloned);
+        playback_timer_cloned.start(TimerMode::Repeated, Duration::from_millis(10), move || {
+            let game_config = &*game_config_cloned.borrow();
+            let tiles = &mut *tiles_cloned.borrow_mut();
+            let elapsed = started.elapsed().as_millis() as u32;
+            let mut lost = false;
+            while let Some(event) = events.get(next_event.get())
+                && event.time_ms <= elapsed
+                && !lost
+            {
+                lost = apply_event(game_config, event, tiles).is_some();
+                next_event.set(next_event.get() + 1);
+            }
+            let main_window = main_window_weak.unwrap();
+            show_board(&main_window, game_config, tiles);
+            main_window.set_flags(remaining_flags(game_config, tiles));
+            main_window.set_time_text(format_time(Duration::from_millis(elapsed as u64)).into());
+            if lost || next_event.get() == events.len() {
+                // Finish on the time of the last played event
+                if let Some(event) = next_event.get().checked_sub(1).and_then(|i| events.get(i)) {
+                    main_window.set_time_text(
+                        format_time(Duration::from_millis(event.time_ms as u64)).into(),
+                    );
+                }
+                main_window.set_replaying(false);
+                main_window.set_state(if lost {
+                    GameState::Lose
+                } else {
+                    board_state(game_config, tiles)
+                });
+                if let Some(playback_timer) = playback_timer_weak.upgrade() {
+                    playback_timer.stop();
+                }
+            }
+        });
+    });
+
+    // Palette Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    let dialogs_cloned = dialogs.clone();
+    main_window.on_palette_changed(move |palette| {
+        settings_cloned.borrow_mut().palette = palette;
+        if let Err(err) = settings_cloned.borrow().save() {
+            eprintln!("Failed to save settings: {err}");
+        }
+        retheme_dialogs(&main_window_weak.unwrap(), &dialogs_cloned);
+    });
+
+    // Appearance Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    let dialogs_cloned = dialogs.clone();
+    main_window.on_appearance_changed(move |appearance| {
+        settings_cloned.borrow_mut().appearance = appearance;
+        if let Err(err) = settings_cloned.borrow().save() {
+            eprintln!("Failed to save settings: {err}");
+        }
+        retheme_dialogs(&main_window_weak.unwrap(), &dialogs_cloned);
+    });
+
+    // Zoom Changed
+    let game_config_cloned = game_config.clone();
+    let settings_cloned = settings.clone();
+    main_window.on_zoom_changed(move |tile_size, fit_to_window| {
+        let zoom = BoardZoom {
+            tile_size,
+            fit_to_window,
+        };
+        settings_cloned
+            .borrow_mut()
+            .set_zoom(&game_config_cloned.borrow(), zoom);
+        if let Err(err) = settings_cloned.borrow().save() {
+            eprintln!("Failed to save settings: {err}");
+        }
+    });
+
+    // Skin Changed
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_skin_changed(move |name| {
+        if let Err(err) = apply_skin(&main_window_weak.unwrap(), &name) {
+            show_error("Skin", &err.to_string());
+            return;
+        }
+        settings_cloned.borrow_mut().skin = name.into();
+        if let Err(err) = settings_cloned.borrow().save() {
+            eprintln!("Failed to save settings: {err}");
+        }
+    });
+
+    // Reload Skins
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
+    main_window.on_reload_skins(move || {
+        let main_window = main_window_weak.unwrap();
+        main_window.set_skins(skin_names(&main_window));
+        if let Err(err) = apply_skin(&main_window, &settings_cloned.borrow().skin) {
+            show_error("Skin", &err.to_string());
+        }
+    });
+
+    // Save Replay
+    let replay_cloned = replay.clone();
+    main_window.on_save_replay(move || {
+        let replay = replay_cloned.borrow();
+        let Some(replay) = &*replay else {
+            show_error("Save Replay", "There is no game to save yet");
+            return;
+        };
+        let data = match write_rmv(replay) {
+            Ok(data) => data,
+            Err(err) => {
+                show_error("Save Replay", &err.to_string());
+                return;
+            }
+        };
+        let Some(path) = FileDialog::new()
+            .set_title("Save Replay")
+            .add_filter("Minesweeper Clone Replay", &["rmv"])
+            .set_file_name("replay.rmv")
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(err) = fs::write(&path, data) {
+            show_error("Save Replay", &err.to_string());
+        }
     });
 
     // About
+    let main_window_weak = main_window.as_weak();
     let about_dialog_cloned = about_dialog.clone();
     main_window.on_about(move || {
         create_about_dialog(about_dialog_cloned.clone());
         let about_dialog = about_dialog_cloned.borrow();
         let about_dialog = about_dialog.as_ref().unwrap();
+        copy_theme(&main_window_weak.unwrap(), about_dialog);
         if about_dialog.window().is_visible() {
             about_dialog.hide().unwrap();
         }
         about_dialog.show().unwrap();
     });
 
+    // Statistics
+    let main_window_weak = main_window.as_weak();
+    let statistics_dialog_cloned = statistics_dialog.clone();
+    let statistics_cloned = statistics.clone();
+    main_window.on_statistics(move || {
+        create_statistics_dialog(statistics_dialog_cloned.clone(), statistics_cloned.clone());
+        let statistics_dialog = statistics_dialog_cloned.borrow();
+        let statistics_dialog = statistics_dialog.as_ref().unwrap();
+        copy_theme(&main_window_weak.unwrap(), statistics_dialog);
+        statistics_dialog.set_rows(statistics_cloned.borrow().create_model());
+        if statistics_dialog.window().is_visible() {
+            statistics_dialog.hide().unwrap();
+        }
+        statistics_dialog.show().unwrap();
+    });
+
+    // High Scores
+    let main_window_weak = main_window.as_weak();
+    let high_scores_dialog_cloned = high_scores_dialog.clone();
+    let high_scores_cloned = high_scores.clone();
+    let game_config_cloned = game_config.clone();
+    main_window.on_high_scores(move || {
+        create_high_scores_dialog(high_scores_dialog_cloned.clone(), high_scores_cloned.clone());
+        let high_scores_dialog = high_scores_dialog_cloned.borrow();
+        let high_scores_dialog = high_scores_dialog.as_ref().unwrap();
+        copy_theme(&main_window_weak.unwrap(), high_scores_dialog);
+        let high_scores = high_scores_cloned.borrow();
+        // Showing the table of the current difficulty if there is one
+        let current_table = config_name(&game_config_cloned.borrow());
+        let current_table = if high_scores.tables.contains_key(&current_table) {
+            current_table
+        } else {
+            high_scores.tables.keys().next().cloned().unwrap_or_default()
+        };
+        high_scores_dialog.set_tables(high_scores.create_tables_model());
+        high_scores_dialog.set_current_table(current_table.as_str().into());
+        high_scores_dialog.set_rows(high_scores.create_model(&current_table));
+        if high_scores_dialog.window().is_visible() {
+            high_scores_dialog.hide().unwrap();
+        }
+        high_scores_dialog.show().unwrap();
+    });
+
+    // Settings
+    let main_window_weak = main_window.as_weak();
+    let settings_dialog_cloned = settings_dialog.clone();
+    let settings_cloned = settings.clone();
+    let state_dialog_cloned = state_dialog.clone();
+    main_window.on_settings(move || {
+        let main_window = main_window_weak.unwrap();
+        create_settings_dialog(
+            settings_dialog_cloned.clone(),
+            main_window.as_weak(),
+            settings_cloned.clone(),
+            state_dialog_cloned.clone(),
+        );
+        let settings_dialog = settings_dialog_cloned.borrow();
+        let settings_dialog = settings_dialog.as_ref().unwrap();
+        copy_theme(&main_window, settings_dialog);
+        fill_settings_dialog(&main_window, settings_dialog, &settings_cloned.borrow());
+        if settings_dialog.window().is_visible() {
+            settings_dialog.hide().unwrap();
+        }
+        settings_dialog.show().unwrap();
+    });
+
     // Closing other windows
     let about_dialog_cloned = about_dialog.clone();
     let state_dialog_cloned = state_dialog.clone();
+    let statistics_dialog_cloned = statistics_dialog.clone();
+    let high_scores_dialog_cloned = high_scores_dialog.clone();
+    let settings_dialog_cloned = settings_dialog.clone();
+    let main_window_weak = main_window.as_weak();
+    let settings_cloned = settings.clone();
     main_window.window().on_close_requested(move || {
+        // Window Geometry
+        let settings = &mut *settings_cloned.borrow_mut();
+        if settings.remember_window {
+            let window = main_window_weak.unwrap();
+            let window = window.window();
+            // A maximized window keeps the size and position it restores to
+            let maximized = window.is_maximized();
+            let geometry = match settings.window {
+                Some(geometry) if maximized => WindowGeometry {
+                    maximized,
+                    ..geometry
+                },
+                _ => WindowGeometry {
+                    x: window.position().x,
+                    y: window.position().y,
+                    width: window.size().width,
+                    height: window.size().height,
+                    maximized,
+                },
+            };
+            settings.window = Some(geometry);
+            if let Err(err) = settings.save() {
+                eprintln!("Failed to save settings: {err}");
+            }
+        }
         // About Dialog
         let about_dialog = about_dialog_cloned.borrow();
         let about_dialog = about_dialog.as_ref();
-        if let Some(about_dialog) = about_dialog {
-            if about_dialog.window().is_visible() {
-                about_dialog.hide().unwrap();
-            }
+        if let Some(about_dialog) = about_dialog
+            && about_dialog.window().is_visible()
+        {
+            about_dialog.hide().unwrap();
         }
         // State Dialog
         let state_dialog = state_dialog_cloned.borrow();
         let state_dialog = state_dialog.as_ref();
-        if let Some(state_dialog) = state_dialog {
-            if state_dialog.window().is_visible() {
-                state_dialog.hide().unwrap();
-            }
+        if let Some(state_dialog) = state_dialog
+            && state_dialog.window().is_visible()
+        {
+            state_dialog.hide().unwrap();
+        }
+        // Statistics Dialog
+        let statistics_dialog = statistics_dialog_cloned.borrow();
+        let statistics_dialog = statistics_dialog.as_ref();
+        if let Some(statistics_dialog) = statistics_dialog
+            && statistics_dialog.window().is_visible()
+        {
+            statistics_dialog.hide().unwrap();
+        }
+        // High Scores Dialog
+        let high_scores_dialog = high_scores_dialog_cloned.borrow();
+        let high_scores_dialog = high_scores_dialog.as_ref();
+        if let Some(high_scores_dialog) = high_scores_dialog
+            && high_scores_dialog.window().is_visible()
+        {
+            high_scores_dialog.hide().unwrap();
+        }
+        // Settings Dialog
+        let settings_dialog = settings_dialog_cloned.borrow();
+        let settings_dialog = settings_dialog.as_ref();
+        if let Some(settings_dialog) = settings_dialog
+            && settings_dialog.window().is_visible()
+        {
+            settings_dialog.hide().unwrap();
         }
         // Closing finally
         slint::CloseRequestResponse::HideWindow
@@ -206,13 +1278,38 @@ fn main() -> Result<(), slint::PlatformError> {
     main_window.run()
 }
 
-fn create_state_dialog(state_dialog: Rc<RefCell<Option<StateDialog>>>, font_size: f32) {
+fn create_state_dialog(
+    state_dialog: Rc<RefCell<Option<StateDialog>>>,
+    font_size: f32,
+    high_scores: Rc<RefCell<HighScores>>,
+    pending_score: Rc<RefCell<Option<(GameConfig, HighScore)>>>,
+    replay: Rc<RefCell<Option<Replay>>>,
+) {
     if state_dialog.borrow().is_none() {
         state_dialog.replace(Some(StateDialog::new().unwrap()));
         let state_dialog = state_dialog.borrow();
         let state_dialog = state_dialog.as_ref().unwrap();
         state_dialog.set_text_font_size(font_size);
         let state_dialog_weak = state_dialog.as_weak();
+        state_dialog.on_save_name(move |name| {
+            let name = name.trim();
+            if name.is_empty() {
+                return;
+            }
+            let Some((game_config, mut high_score)) = pending_score.take() else {
+                return;
+            };
+            high_score.name = name.to_string();
+            high_scores.borrow_mut().insert(&game_config, high_score);
+            if let Err(err) = high_scores.borrow().save() {
+                show_error("High Scores", &err.to_string());
+            }
+            if let Some(replay) = &mut *replay.borrow_mut() {
+                replay.player = name.to_string();
+            }
+            state_dialog_weak.unwrap().set_ask_name(false);
+        });
+        let state_dialog_weak = state_dialog.as_weak();
         state_dialog.on_close(move || {
             state_dialog_weak
                 .unwrap()
@@ -222,6 +1319,271 @@ fn create_state_dialog(state_dialog: Rc<RefCell<Option<StateDialog>>>, font_size
     }
 }
 
+// State Dialog at the end of a game, a win shows its summary and the other games how far they got
+#[allow(clippy::too_many_arguments)]
+fn show_result_dialog(
+    main_window: &MainWindow,
+    state_dialog: Rc<RefCell<Option<StateDialog>>>,
+    high_scores: Rc<RefCell<HighScores>>,
+    pending_score: Rc<RefCell<Option<(GameConfig, HighScore)>>>,
+    replay: Rc<RefCell<Option<Replay>>>,
+    state: GameState,
+    summary: GameSummary,
+    results: String,
+) {
+    create_state_dialog(
+        state_dialog.clone(),
+        main_window.get_text_font_size(),
+        high_scores.clone(),
+        pending_score.clone(),
+        replay,
+    );
+    let state_dialog = state_dialog.borrow();
+    let state_dialog = state_dialog.as_ref().unwrap();
+    copy_theme(main_window, state_dialog);
+    state_dialog.set_state(state);
+    state_dialog.set_summary(summary);
+    state_dialog.set_results(results.into());
+    // Only a win can make it into the high scores
+    state_dialog.set_ask_name(state == GameState::Win && pending_score.borrow().is_some());
+    state_dialog.set_player_name(high_scores.borrow().last_name.as_str().into());
+    state_dialog.show().unwrap();
+}
+
+// Same order as the combo boxes of the Settings Dialog
+const PALETTES: [ColorPalette; 3] = [
+    ColorPalette::Standard,
+    ColorPalette::HighContrast,
+    ColorPalette::ColorBlind,
+];
+const APPEARANCES: [Appearance; 3] = [Appearance::System, Appearance::Light, Appearance::Dark];
+
+// Difficulties followed by the custom board
+fn levels_model() -> ModelRc<SharedString> {
+    let mut levels: Vec<SharedString> = GameDifficulty::create_model().iter().collect();
+    levels.push("Custom".into());
+    ModelRc::new(VecModel::from(levels))
+}
+
+fn custom_level_index() -> i32 {
+    GameDifficulty::create_model().row_count() as i32
+}
+
+fn level_index(settings: &Settings) -> i32 {
+    if settings.custom_difficulty {
+        custom_level_index()
+    } else {
+        settings.difficulty.into()
+    }
+}
+
+// Built-in skins first, user skins with the name of a built-in one are left out
+fn skin_names(main_window: &MainWindow) -> ModelRc<SharedString> {
+    let mut names: Vec<SharedString> = main_window
+        .global::<Theme>()
+        .get_built_in_skins()
+        .iter()
+        .map(|skin| skin.name)
+        .collect();
+    for name in user_skins() {
+        if !names.iter().any(|built_in| built_in == name.as_str()) {
+            names.push(name.into());
+        }
+    }
+    ModelRc::new(VecModel::from(names))
+}
+
+fn apply_skin(main_window: &MainWindow, name: &str) -> Result<(), SkinError> {
+    let theme = main_window.global::<Theme>();
+    let built_in_skins = theme.get_built_in_skins();
+    let skin = match built_in_skins.iter().find(|skin| skin.name == name) {
+        Some(skin) => skin,
+        // Missing images of user skins come from the first built-in skin
+        None => load_user_skin(name, &built_in_skins.row_data(0).unwrap())?,
+    };
+    theme.set_skin(skin);
+    Ok(())
+}
+
+fn apply_zoom(main_window: &MainWindow, settings: &Settings, game_config: &GameConfig) {
+    let zoom = settings.zoom(game_config);
+    main_window.set_tile_size(zoom.tile_size);
+    main_window.set_fit_to_window(zoom.fit_to_window);
+}
+
+// New boards start on the first layer
+fn apply_topology(main_window: &MainWindow, game_config: &GameConfig) {
+    main_window.set_hexagonal(game_config.topology == Topology::Hexagonal);
+    main_window.set_wrap_edges(game_config.wrap);
+    main_window.set_layer_count(game_config.layer_count as i32);
+    main_window.set_neighbourhood(game_config.neighbourhood.into());
+    main_window.set_max_neighbours(max_neighbours(game_config) as i32);
+    main_window.set_max_tile_mines(game_config.max_tile_mines as i32);
+    main_window.set_liar(game_config.liar);
+    main_window.set_max_lives(game_config.lives as i32);
+    main_window.set_mode(game_config.mode.into());
+    main_window.set_timed(time_left(game_config, Duration::ZERO).is_some());
+    main_window.set_time_attack(game_config.mode == GameMode::TimeAttack);
+    main_window.set_time_limit(game_config.time_limit_secs as i32);
+    main_window.set_endless(game_config.mode == GameMode::Endless);
+    let (shape, loaded_shape) = match &game_config.mask {
+        None => (Shape::Rectangle.into(), SharedString::new()),
+        Some(mask) => match mask.preset {
+            Some(shape) => (shape.into(), SharedString::new()),
+            None => (-1, mask.name.as_str().into()),
+        },
+    };
+    main_window.set_shape(shape);
+    main_window.set_loaded_shape(loaded_shape);
+    main_window.set_layer(0);
+}
+
+// Shown layer of the board with the layers around it, and the lives left on it
+fn show_board(main_window: &MainWindow, game_config: &GameConfig, tiles: &[Vec<Tile>]) {
+    main_window.set_lives(lives_left(game_config, tiles));
+    let layer = main_window.get_layer().max(0) as usize;
+    main_window.set_grid(vec2d_to_model_grid(layer_rows(game_config, tiles, layer)));
+    let above = match layer.checked_sub(1) {
+        Some(above) => layer_rows(game_config, tiles, above),
+        None => &[],
+    };
+    main_window.set_layer_above(vec2d_to_model_grid(above));
+    main_window.set_layer_below(vec2d_to_model_grid(layer_rows(
+        game_config,
+        tiles,
+        layer + 1,
+    )));
+}
+
+// Dialogs are windows of their own, they take the palette and appearance of the main window
+fn copy_theme<'a, T>(main_window: &MainWindow, dialog: &'a T)
+where
+    T: ComponentHandle,
+    Theme<'a>: slint::Global<'a, T>,
+{
+    let theme = main_window.global::<Theme>();
+    let dialog_theme = dialog.global::<Theme>();
+    dialog_theme.set_palette(theme.get_palette());
+    dialog_theme.set_appearance(theme.get_appearance());
+}
+
+// Dialogs made so far, they follow the palette and appearance of the main window
+#[derive(Clone)]
+struct Dialogs {
+    state: Rc<RefCell<Option<StateDialog>>>,
+    about: Rc<RefCell<Option<AboutDialog>>>,
+    statistics: Rc<RefCell<Option<StatisticsDialog>>>,
+    high_scores: Rc<RefCell<Option<HighScoresDialog>>>,
+    settings: Rc<RefCell<Option<SettingsDialog>>>,
+}
+
+fn retheme_dialogs(main_window: &MainWindow, dialogs: &Dialogs) {
+    if let Some(dialog) = &*dialogs.state.borrow() {
+        copy_theme(main_window, dialog);
+    }
+    if let Some(dialog) = &*dialogs.about.borrow() {
+        copy_theme(main_window, dialog);
+    }
+    if let Some(dialog) = &*dialogs.statistics.borrow() {
+        copy_theme(main_window, dialog);
+    }
+    if let Some(dialog) = &*dialogs.high_scores.borrow() {
+        copy_theme(main_window, dialog);
+    }
+    if let Some(dialog) = &*dialogs.settings.borrow() {
+        copy_theme(main_window, dialog);
+    }
+}
+
+// Timed modes count down to their limit
+fn show_time(main_window: &MainWindow, game_config: &GameConfig, elapsed: Duration) {
+    let shown = time_left(game_config, elapsed).unwrap_or(elapsed);
+    main_window.set_time_text(format_time(shown).into());
+}
+
+fn stop_clock(
+    main_window: &MainWindow,
+    game_config: &GameConfig,
+    clock: &Rc<RefCell<GameClock>>,
+) -> Duration {
+    clock.borrow_mut().stop();
+    let time = clock.borrow().elapsed();
+    show_time(main_window, game_config, time);
+    time
+}
+
+fn reset_clock(main_window: &MainWindow, game_config: &GameConfig, clock: &Rc<RefCell<GameClock>>) {
+    clock.borrow_mut().reset();
+    show_time(main_window, game_config, Duration::ZERO);
+}
+
+// Part of the endless board under the view, kept in tiles like a fixed board
+fn show_endless(
+    main_window: &MainWindow,
+    game_config: &GameConfig,
+    board: &mut EndlessBoard,
+    (top, left): (i64, i64),
+    tiles: &mut Vec<Vec<Tile>>,
+) {
+    tiles.clone_from(&board.view(top, left, game_config.row_count, game_config.col_count));
+    show_board(main_window, game_config, tiles);
+    main_window.set_score(board.score() as i32);
+}
+
+// How far a timed or endless game got, shown by the State Dialog when it did not win
+// Progress is the boards cleared by a time attack or the safe tiles revealed on an endless board
+fn results_text(game_config: &GameConfig, tiles: &[Vec<Tile>], progress: usize) -> String {
+    match game_config.mode {
+        GameMode::Classic => String::new(),
+        GameMode::Countdown => {
+            let safe = tiles
+                .iter()
+                .flatten()
+                .filter(|tile| !tile.disabled && tile.value != MINE_VALUE);
+            let revealed = safe.clone().filter(|tile| tile.visible).count();
+            format!("Tiles revealed: {revealed} of {}", safe.count())
+        }
+        GameMode::TimeAttack => format!("Boards cleared: {progress}"),
+        GameMode::Endless => format!("Safe tiles revealed: {progress}"),
+    }
+}
+
+fn record_game(
+    statistics: &Rc<RefCell<Statistics>>,
+    game_config: &GameConfig,
+    tiles: &[Vec<Tile>],
+    replay: &Rc<RefCell<Option<Replay>>>,
+    won: bool,
+    time: Duration,
+) -> GameMetrics {
+    let replay = replay.borrow();
+    let events = replay.as_ref().map_or(&[][..], |replay| &replay.events);
+    let metrics = GameMetrics::new(game_config, tiles, events, time);
+    statistics.borrow_mut().record(game_config, won, &metrics);
+    if let Err(err) = statistics.borrow().save() {
+        eprintln!("Failed to save statistics: {err}");
+    }
+    metrics
+}
+
+fn close_state_dialog(state_dialog: Rc<RefCell<Option<StateDialog>>>) {
+    if state_dialog.borrow().is_some() {
+        let state_dialog = state_dialog.borrow();
+        let state_dialog = state_dialog.as_ref().unwrap();
+        state_dialog
+            .window()
+            .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+    }
+}
+
+fn show_error(title: &str, description: &str) {
+    MessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title(title)
+        .set_description(description)
+        .show();
+}
+
 fn create_about_dialog(about_dialog: Rc<RefCell<Option<AboutDialog>>>) {
     if about_dialog.borrow().is_none() {
         about_dialog.replace(Some(AboutDialog::new().unwrap()));
@@ -240,3 +1602,200 @@ fn create_about_dialog(about_dialog: Rc<RefCell<Option<AboutDialog>>>) {
         });
     }
 }
+
+fn create_statistics_dialog(
+    statistics_dialog: Rc<RefCell<Option<StatisticsDialog>>>,
+    statistics: Rc<RefCell<Statistics>>,
+) {
+    if statistics_dialog.borrow().is_none() {
+        statistics_dialog.replace(Some(StatisticsDialog::new().unwrap()));
+        let statistics_dialog = statistics_dialog.borrow();
+        let statistics_dialog = statistics_dialog.as_ref().unwrap();
+        let statistics_dialog_weak = statistics_dialog.as_weak();
+        statistics_dialog.on_reset(move || {
+            let confirmed = MessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title("Reset Statistics")
+                .set_description("All statistics will be lost, are you sure?")
+                .set_buttons(MessageButtons::YesNo)
+                .show();
+            if confirmed == MessageDialogResult::Yes {
+                statistics.borrow_mut().reset();
+                if let Err(err) = statistics.borrow().save() {
+                    show_error("Reset Statistics", &err.to_string());
+                }
+                statistics_dialog_weak
+                    .unwrap()
+                    .set_rows(statistics.borrow().create_model());
+            }
+        });
+        let statistics_dialog_weak = statistics_dialog.as_weak();
+        statistics_dialog.on_close(move || {
+            statistics_dialog_weak
+                .unwrap()
+                .window()
+                .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+        });
+    }
+}
+
+fn create_high_scores_dialog(
+    high_scores_dialog: Rc<RefCell<Option<HighScoresDialog>>>,
+    high_scores: Rc<RefCell<HighScores>>,
+) {
+    if high_scores_dialog.borrow().is_none() {
+        high_scores_dialog.replace(Some(HighScoresDialog::new().unwrap()));
+        let high_scores_dialog = high_scores_dialog.borrow();
+        let high_scores_dialog = high_scores_dialog.as_ref().unwrap();
+        let high_scores_dialog_weak = high_scores_dialog.as_weak();
+        let high_scores_cloned = high_scores.clone();
+        high_scores_dialog.on_table_changed(move |table| {
+            high_scores_dialog_weak
+                .unwrap()
+                .set_rows(high_scores_cloned.borrow().create_model(&table));
+        });
+        high_scores_dialog.on_export_csv(move || {
+            let Some(path) = FileDialog::new()
+                .set_title("Export High Scores")
+                .add_filter("CSV", &["csv"])
+                .set_file_name("high_scores.csv")
+                .save_file()
+            else {
+                return;
+            };
+            if let Err(err) = fs::write(&path, high_scores.borrow().to_csv()) {
+                show_error("Export High Scores", &err.to_string());
+            }
+        });
+        let high_scores_dialog_weak = high_scores_dialog.as_weak();
+        high_scores_dialog.on_close(move || {
+            high_scores_dialog_weak
+                .unwrap()
+                .window()
+                .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+        });
+    }
+}
+
+fn create_settings_dialog(
+    settings_dialog: Rc<RefCell<Option<SettingsDialog>>>,
+    main_window_weak: slint::Weak<MainWindow>,
+    settings: Rc<RefCell<Settings>>,
+    state_dialog: Rc<RefCell<Option<StateDialog>>>,
+) {
+    if settings_dialog.borrow().is_none() {
+        settings_dialog.replace(Some(SettingsDialog::new().unwrap()));
+        let settings_dialog = settings_dialog.borrow();
+        let settings_dialog = settings_dialog.as_ref().unwrap();
+        settings_dialog.set_first_clicks(FirstClick::create_model());
+        let settings_dialog_weak = settings_dialog.as_weak();
+        settings_dialog.on_save(move || {
+            let settings_dialog = settings_dialog_weak.unwrap();
+            let main_window = main_window_weak.unwrap();
+            let old = settings.borrow().clone();
+            let mut edited = old.clone();
+            edited.font_size = settings_dialog.get_font_size() as f32;
+            edited.custom = GameConfig {
+                row_count: settings_dialog.get_custom_rows().max(0) as usize,
+                col_count: settings_dialog.get_custom_cols().max(0) as usize,
+                mine_count: settings_dialog.get_custom_mines().max(0) as usize,
+                ..Default::default()
+            };
+            edited.first_click = settings_dialog.get_first_click().into();
+            edited.key_bindings.up = settings_dialog.get_up_key().trim().into();
+            edited.key_bindings.down = settings_dialog.get_down_key().trim().into();
+            edited.key_bindings.left = settings_dialog.get_left_key().trim().into();
+            edited.key_bindings.right = settings_dialog.get_right_key().trim().into();
+            edited.key_bindings.reveal = settings_dialog.get_reveal_key().trim().into();
+            edited.key_bindings.flag = settings_dialog.get_flag_key().trim().into();
+            edited.key_bindings.chord = settings_dialog.get_chord_key().trim().into();
+            edited.key_bindings.pause = settings_dialog.get_pause_key().trim().into();
+            edited.remember_window = settings_dialog.get_remember_window();
+            if !edited.remember_window {
+                edited.window = None;
+            }
+            if let Err(err) = edited.validate() {
+                show_error("Settings", &err.to_string());
+                return;
+            }
+            settings.replace(edited.clone());
+            if let Err(err) = settings.borrow().save() {
+                show_error("Settings", &err.to_string());
+            }
+
+            main_window.set_text_font_size(edited.font_size);
+            if let Some(state_dialog) = &*state_dialog.borrow() {
+                state_dialog.set_text_font_size(edited.font_size);
+            }
+            main_window.set_key_bindings(edited.key_bindings.clone());
+            // The theme callbacks save the settings and pass the theme to the dialogs
+            let palette = PALETTES[settings_dialog.get_palette().clamp(0, 2) as usize];
+            if palette != old.palette {
+                main_window.global::<Theme>().set_palette(palette);
+                main_window.invoke_palette_changed(palette);
+            }
+            let appearance = APPEARANCES[settings_dialog.get_appearance().clamp(0, 2) as usize];
+            if appearance != old.appearance {
+                main_window.global::<Theme>().set_appearance(appearance);
+                main_window.invoke_appearance_changed(appearance);
+            }
+            if let Some(skin) = main_window
+                .get_skins()
+                .row_data(settings_dialog.get_skin().max(0) as usize)
+                && skin != old.skin.as_str()
+            {
+                main_window.invoke_skin_changed(skin);
+            }
+            // A changed custom board replaces the current one only before the game starts
+            if edited.custom_difficulty
+                && edited.custom != old.custom
+                && main_window.get_state() == GameState::Initial
+                && !main_window.get_replaying()
+            {
+                main_window.invoke_level_changed(custom_level_index());
+            }
+            settings_dialog
+                .window()
+                .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+        });
+        let settings_dialog_weak = settings_dialog.as_weak();
+        settings_dialog.on_close(move || {
+            settings_dialog_weak
+                .unwrap()
+                .window()
+                .dispatch_event(slint::platform::WindowEvent::CloseRequested);
+        });
+    }
+}
+
+fn fill_settings_dialog(
+    main_window: &MainWindow,
+    settings_dialog: &SettingsDialog,
+    settings: &Settings,
+) {
+    let skins = main_window.get_skins();
+    let skin = skins.iter().position(|skin| skin == settings.skin.as_str());
+    let palette = PALETTES.iter().position(|palette| *palette == settings.palette);
+    let appearance = APPEARANCES
+        .iter()
+        .position(|appearance| *appearance == settings.appearance);
+    let bindings = &settings.key_bindings;
+    settings_dialog.set_font_size(settings.font_size.round() as i32);
+    settings_dialog.set_custom_rows(settings.custom.row_count as i32);
+    settings_dialog.set_custom_cols(settings.custom.col_count as i32);
+    settings_dialog.set_custom_mines(settings.custom.mine_count as i32);
+    settings_dialog.set_first_click(settings.first_click.into());
+    settings_dialog.set_palette(palette.unwrap_or_default() as i32);
+    settings_dialog.set_appearance(appearance.unwrap_or_default() as i32);
+    settings_dialog.set_skins(skins);
+    settings_dialog.set_skin(skin.unwrap_or_default() as i32);
+    settings_dialog.set_up_key(bindings.up.clone());
+    settings_dialog.set_down_key(bindings.down.clone());
+    settings_dialog.set_left_key(bindings.left.clone());
+    settings_dialog.set_right_key(bindings.right.clone());
+    settings_dialog.set_reveal_key(bindings.reveal.clone());
+    settings_dialog.set_flag_key(bindings.flag.clone());
+    settings_dialog.set_chord_key(bindings.chord.clone());
+    settings_dialog.set_pause_key(bindings.pause.clone());
+    settings_dialog.set_remember_window(settings.remember_window);
+}
@@ -0,0 +1,211 @@
+// Plain text layout of a board, one line per row:
+//   `.` hidden safe tile      `*` hidden mine
+//   `0`-`8` revealed safe tile (must match the mines around it)
+//   `f` flagged safe tile     `F` flagged mine
+//   `X` revealed mine
+// Empty lines and lines starting with `#` are ignored.
+
+use std::{error::Error, fmt};
+
+use super::{GameConfig, MINE_VALUE, Tile, fill_numbers};
+
+pub const HIDDEN_SAFE: char = '.';
+pub const HIDDEN_MINE: char = '*';
+pub const FLAGGED_SAFE: char = 'f';
+pub const FLAGGED_MINE: char = 'F';
+pub const REVEALED_MINE: char = 'X';
+pub const COMMENT: char = '#';
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BoardTextError {
+    Empty,
+    TooSmall,
+    RaggedRow { row: usize },
+    UnknownSymbol { row: usize, col: usize, symbol: char },
+    NumberMismatch { row: usize, col: usize },
+    // Only numbers up to 8 and one mine or flag per tile without gaps can be written
+    NotPlain { row: usize, col: usize },
+}
+
+impl fmt::Display for BoardTextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardTextError::Empty => write!(f, "board has no rows"),
+            BoardTextError::TooSmall => write!(f, "board must be at least 2x2"),
+            BoardTextError::RaggedRow { row } => {
+                write!(f, "row {} has a different length than the first row", row + 1)
+            }
+            BoardTextError::UnknownSymbol { row, col, symbol } => write!(
+                f,
+                "unknown symbol '{}' at row {}, column {}",
+                symbol,
+                row + 1,
+                col + 1
+            ),
+            BoardTextError::NumberMismatch { row, col } => write!(
+                f,
+                "number at row {}, column {} does not match the mines around it",
+                row + 1,
+                col + 1
+            ),
+            BoardTextError::NotPlain { row, col } => write!(
+                f,
+                "tile at row {}, column {} can not be written as a single symbol",
+                row + 1,
+                col + 1
+            ),
+        }
+    }
+}
+
+impl Error for BoardTextError {}
+
+pub fn parse_board(text: &str) -> Result<(GameConfig, Vec<Vec<Tile>>), BoardTextError> {
+    let mut tiles: Vec<Vec<Tile>> = Vec::new();
+    // Numbers written in the text, checked once every mine is known
+    let mut numbers = Vec::new();
+
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(COMMENT));
+    for (i, line) in lines.enumerate() {
+        let mut row_vec = Vec::new();
+        for (j, symbol) in line.chars().enumerate() {
+            let (value, visible, flagged) = match symbol {
+                HIDDEN_SAFE => (0, false, false),
+                HIDDEN_MINE => (MINE_VALUE, false, false),
+                FLAGGED_SAFE => (0, false, true),
+                FLAGGED_MINE => (MINE_VALUE, false, true),
+                REVEALED_MINE => (MINE_VALUE, true, false),
+                '0'..='8' => {
+                    numbers.push((i, j, symbol.to_digit(10).unwrap() as i32));
+                    (0, true, false)
+                }
+                _ => return Err(BoardTextError::UnknownSymbol { row: i, col: j, symbol }),
+            };
+            row_vec.push(Tile {
+                value,
+                mines: (value == MINE_VALUE) as i32,
+                visible,
+                flags: flagged as i32,
+                disabled: false,
+                exploded: false,
+            });
+        }
+        if let Some(first) = tiles.first()
+            && first.len() != row_vec.len()
+        {
+            return Err(BoardTextError::RaggedRow { row: i });
+        }
+        tiles.push(row_vec);
+    }
+
+    if tiles.is_empty() {
+        return Err(BoardTextError::Empty);
+    }
+    if tiles.len() < 2 || tiles[0].len() < 2 {
+        return Err(BoardTextError::TooSmall);
+    }
+
+    let game_config = GameConfig {
+        row_count: tiles.len(),
+        col_count: tiles[0].len(),
+        mine_count: tiles
+            .iter()
+            .flatten()
+            .filter(|tile| tile.value == MINE_VALUE)
+            .count(),
+        ..Default::default()
+    };
+    fill_numbers(&game_config, &mut tiles);
+
+    for (row, col, number) in numbers {
+        if tiles[row][col].value != number {
+            return Err(BoardTextError::NumberMismatch { row, col });
+        }
+    }
+
+    Ok((game_config, tiles))
+}
+
+pub fn export_board(tiles: &[Vec<Tile>]) -> Result<String, BoardTextError> {
+    let mut text = String::new();
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if tile.disabled || tile.mines > 1 || tile.flags > 1 {
+                return Err(BoardTextError::NotPlain { row: i, col: j });
+            }
+            let symbol = match (tile.value == MINE_VALUE, tile.visible, tile.flags > 0) {
+                (true, true, _) => REVEALED_MINE,
+                (true, false, true) => FLAGGED_MINE,
+                (true, false, false) => HIDDEN_MINE,
+                (false, true, _) => match u32::try_from(tile.value) {
+                    Ok(value @ 0..=8) => char::from_digit(value, 10).unwrap(),
+                    _ => return Err(BoardTextError::NotPlain { row: i, col: j }),
+                },
+                (false, false, true) => FLAGGED_SAFE,
+                (false, false, false) => HIDDEN_SAFE,
+            };
+            text.push(symbol);
+        }
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/board.txt");
+
+    #[test]
+    fn export_writes_back_the_parsed_board() {
+        let (game_config, tiles) = parse_board(FIXTURE).unwrap();
+        assert_eq!((game_config.row_count, game_config.col_count), (5, 6));
+        assert_eq!(game_config.mine_count, 4);
+        let expected: String = FIXTURE
+            .lines()
+            .filter(|line| !line.starts_with(COMMENT))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert_eq!(export_board(&tiles), Ok(expected));
+    }
+
+    #[test]
+    fn export_refuses_numbers_above_eight() {
+        let (_, mut tiles) = parse_board(FIXTURE).unwrap();
+        tiles[0][3].value = 10;
+        assert_eq!(
+            export_board(&tiles),
+            Err(BoardTextError::NotPlain { row: 0, col: 3 })
+        );
+    }
+
+    #[test]
+    fn export_refuses_stacked_mines_and_flags() {
+        let (_, mut tiles) = parse_board(FIXTURE).unwrap();
+        tiles[0][1].mines = 2;
+        assert_eq!(
+            export_board(&tiles),
+            Err(BoardTextError::NotPlain { row: 0, col: 1 })
+        );
+        tiles[0][1].mines = 1;
+        tiles[4][0].flags = 2;
+        assert_eq!(
+            export_board(&tiles),
+            Err(BoardTextError::NotPlain { row: 4, col: 0 })
+        );
+    }
+
+    #[test]
+    fn export_refuses_gaps() {
+        let (_, mut tiles) = parse_board(FIXTURE).unwrap();
+        tiles[2][5].disabled = true;
+        assert_eq!(
+            export_board(&tiles),
+            Err(BoardTextError::NotPlain { row: 2, col: 5 })
+        );
+    }
+}
@@ -0,0 +1,63 @@
+// Monotonic game time, started by the first reveal and stopped when the game ends
+
+use std::time::{Duration, Instant};
+
+use super::{GameConfig, GameMode};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GameClock {
+    // Time counted before the last pause
+    counted: Duration,
+    running_since: Option<Instant>,
+}
+
+impl GameClock {
+    pub fn start(&mut self) {
+        self.counted = Duration::ZERO;
+        self.running_since = Some(Instant::now());
+    }
+
+    // Pausing and stopping both freeze the time, only resume tells them apart
+    pub fn stop(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.counted += since.elapsed();
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.stop();
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.counted = Duration::ZERO;
+        self.running_since = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.counted + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+// Time before the limit of a timed mode runs out, None when the game is not played against the clock
+pub fn time_left(game_config: &GameConfig, elapsed: Duration) -> Option<Duration> {
+    match game_config.mode {
+        GameMode::Classic | GameMode::Endless => None,
+        GameMode::Countdown | GameMode::TimeAttack => {
+            Some(Duration::from_secs(game_config.time_limit_secs).saturating_sub(elapsed))
+        }
+    }
+}
+
+pub fn is_time_up(game_config: &GameConfig, elapsed: Duration) -> bool {
+    time_left(game_config, elapsed).is_some_and(|left| left.is_zero())
+}
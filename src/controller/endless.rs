@@ -0,0 +1,240 @@
+// Board without edges, made of square chunks which get their mines from the seed when first reached
+// Tiles are addressed by signed (row, col), the board only keeps the chunks generated so far
+
+use std::collections::{HashMap, VecDeque};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use super::{GameConfig, GameOver, MINE_VALUE, Tile};
+
+pub const CHUNK_SIZE: i64 = 16;
+// Largest opening made by a single reveal, sparse boards would otherwise open without end
+pub const MAX_OPENING: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct EndlessBoard {
+    seed: u64,
+    // Chance of a tile to hold a mine
+    density: f64,
+    // The tiles around the first reveal never hold a mine
+    start: (i64, i64),
+    // Tiles of each chunk row by row, keyed by the chunk row and column
+    chunks: HashMap<(i64, i64), Vec<Tile>>,
+    // Safe tiles revealed, the score of the game
+    revealed: usize,
+    lost: bool,
+}
+
+impl EndlessBoard {
+    pub fn new(seed: u64, density: f64, start: (i64, i64)) -> Self {
+        Self {
+            seed,
+            density: density.clamp(0.0, 1.0),
+            start,
+            chunks: HashMap::new(),
+            revealed: 0,
+            lost: false,
+        }
+    }
+
+    // Same density of mines as the fixed board of the config
+    pub fn density(game_config: &GameConfig) -> f64 {
+        game_config.mine_count as f64
+            / (game_config.row_count * game_config.col_count).max(1) as f64
+    }
+
+    pub fn score(&self) -> usize {
+        self.revealed
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    pub fn tile(&mut self, row: i64, col: i64) -> Tile {
+        self.tile_mut(row, col).clone()
+    }
+
+    // Tiles of the board seen through a window, mines are all shown once the game is lost
+    pub fn view(
+        &mut self,
+        top: i64,
+        left: i64,
+        row_count: usize,
+        col_count: usize,
+    ) -> Vec<Vec<Tile>> {
+        let lost = self.lost;
+        (top..top + row_count as i64)
+            .map(|row| {
+                (left..left + col_count as i64)
+                    .map(|col| {
+                        let mut tile = self.tile(row, col);
+                        if lost && tile.value == MINE_VALUE && tile.flags == 0 {
+                            tile.visible = true;
+                        }
+                        tile
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn toggle_flag(&mut self, row: i64, col: i64) {
+        let tile = self.tile_mut(row, col);
+        if !tile.visible {
+            tile.flags = if tile.flags > 0 { 0 } else { 1 };
+        }
+    }
+
+    #[must_use]
+    pub fn reveal(&mut self, row: i64, col: i64) -> Option<GameOver> {
+        let tile = self.tile(row, col);
+        if tile.visible || tile.flags > 0 {
+            return None;
+        }
+        if tile.value == MINE_VALUE {
+            let tile = self.tile_mut(row, col);
+            tile.visible = true;
+            tile.exploded = true;
+            self.lost = true;
+            return Some(GameOver);
+        }
+        // Opening of the blanks around the tile
+        let mut queue = VecDeque::from([(row, col)]);
+        let mut opened = 0;
+        while let Some((row, col)) = queue.pop_front() {
+            let tile = self.tile(row, col);
+            if tile.visible || tile.flags > 0 {
+                continue;
+            }
+            if opened == MAX_OPENING {
+                break;
+            }
+            let value = self.mines_around(row, col);
+            let tile = self.tile_mut(row, col);
+            tile.visible = true;
+            tile.value = value;
+            self.revealed += 1;
+            opened += 1;
+            if value == 0 {
+                queue.extend(neighbours(row, col));
+            }
+        }
+        None
+    }
+
+    // Reveals the tiles around a number once it has as many flags around it
+    #[must_use]
+    pub fn chord(&mut self, row: i64, col: i64) -> Option<GameOver> {
+        let tile = self.tile(row, col);
+        if !tile.visible || tile.value <= 0 {
+            return None;
+        }
+        let flags: i32 = neighbours(row, col)
+            .map(|(row, col)| self.tile(row, col).flags)
+            .sum();
+        if flags != tile.value {
+            return None;
+        }
+        let mut lost = None;
+        for (row, col) in neighbours(row, col) {
+            lost = lost.or(self.reveal(row, col));
+        }
+        lost
+    }
+
+    fn mines_around(&mut self, row: i64, col: i64) -> i32 {
+        neighbours(row, col)
+            .filter(|(row, col)| self.tile(*row, *col).value == MINE_VALUE)
+            .count() as i32
+    }
+
+    fn tile_mut(&mut self, row: i64, col: i64) -> &mut Tile {
+        let chunk = (row.div_euclid(CHUNK_SIZE), col.div_euclid(CHUNK_SIZE));
+        let index = row.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + col.rem_euclid(CHUNK_SIZE);
+        if !self.chunks.contains_key(&chunk) {
+            let tiles = self.generate(chunk);
+            self.chunks.insert(chunk, tiles);
+        }
+        &mut self.chunks.get_mut(&chunk).unwrap()[index as usize]
+    }
+
+    // Every chunk has its own generator seeded from the board seed, so it comes out the same in any order
+    fn generate(&self, (chunk_row, chunk_col): (i64, i64)) -> Vec<Tile> {
+        let chunk_seed = self.seed
+            ^ (chunk_row as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (chunk_col as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        let mut rng = StdRng::seed_from_u64(chunk_seed);
+        let mut tiles = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+        for i in 0..CHUNK_SIZE {
+            for j in 0..CHUNK_SIZE {
+                let (row, col) = (chunk_row * CHUNK_SIZE + i, chunk_col * CHUNK_SIZE + j);
+                let near_start = (row - self.start.0).abs() <= 1 && (col - self.start.1).abs() <= 1;
+                let mine = rng.random_bool(self.density) && !near_start;
+                tiles.push(Tile {
+                    value: if mine { MINE_VALUE } else { 0 },
+                    mines: mine as i32,
+                    visible: false,
+                    flags: 0,
+                    disabled: false,
+                    exploded: false,
+                });
+            }
+        }
+        tiles
+    }
+}
+
+fn neighbours(row: i64, col: i64) -> impl Iterator<Item = (i64, i64)> {
+    (-1..=1)
+        .flat_map(move |i| (-1..=1).map(move |j| (row + i, col + j)))
+        .filter(move |position| *position != (row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mines(board: &mut EndlessBoard, top: i64, left: i64) -> Vec<(i64, i64)> {
+        let mut mines = Vec::new();
+        for row in top..top + CHUNK_SIZE {
+            for col in left..left + CHUNK_SIZE {
+                if board.tile(row, col).value == MINE_VALUE {
+                    mines.push((row, col));
+                }
+            }
+        }
+        mines
+    }
+
+    #[test]
+    fn chunks_come_from_the_seed_in_any_order() {
+        let mut board = EndlessBoard::new(7, 0.3, (0, 0));
+        let first = mines(&mut board, 5 * CHUNK_SIZE, -3 * CHUNK_SIZE);
+        assert!(!first.is_empty());
+        // Reaching the chunk after others gives it the same mines
+        let mut other = EndlessBoard::new(7, 0.3, (0, 0));
+        mines(&mut other, -CHUNK_SIZE, CHUNK_SIZE);
+        mines(&mut other, 4 * CHUNK_SIZE, -3 * CHUNK_SIZE);
+        assert_eq!(mines(&mut other, 5 * CHUNK_SIZE, -3 * CHUNK_SIZE), first);
+        let mut reseeded = EndlessBoard::new(8, 0.3, (0, 0));
+        assert_ne!(mines(&mut reseeded, 5 * CHUNK_SIZE, -3 * CHUNK_SIZE), first);
+    }
+
+    #[test]
+    fn start_is_an_opening() {
+        let mut board = EndlessBoard::new(3, 1.0, (-20, 40));
+        assert_eq!(board.reveal(-20, 40), None);
+        assert_eq!(board.tile(-20, 40).value, 0);
+        assert_eq!(board.tile(-21, 39).value, 5);
+        assert_eq!(board.score(), 9);
+    }
+
+    #[test]
+    fn opening_stops_at_the_cap() {
+        let mut board = EndlessBoard::new(1, 0.0, (0, 0));
+        assert_eq!(board.reveal(0, 0), None);
+        assert_eq!(board.score(), MAX_OPENING);
+        assert!(!board.is_lost());
+    }
+}
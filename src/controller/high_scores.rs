@@ -0,0 +1,138 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use slint::{ModelRc, SharedString, StandardListViewItem, VecModel};
+
+use super::{FORMAT_VERSION, GameConfig, format_time, statistics::config_name};
+
+#[cfg(feature = "persistence")]
+pub const HIGH_SCORES_FILE: &str = "high_scores.json";
+pub const HIGH_SCORES_PER_TABLE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighScore {
+    pub name: String,
+    pub time_ms: u64,
+    pub date: String,
+    pub three_bv: u32,
+    pub efficiency: f64,
+}
+
+impl HighScore {
+    pub fn time(&self) -> Duration {
+        Duration::from_millis(self.time_ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighScores {
+    pub format_version: u32,
+    // Name entered for the last high score, offered again for the next one
+    pub last_name: String,
+    // Keyed by config_name, fastest first
+    pub tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            last_name: String::new(),
+            tables: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl super::storage::Versioned for HighScores {
+    fn format_version_mut(&mut self) -> &mut u32 {
+        &mut self.format_version
+    }
+}
+
+impl HighScores {
+    pub fn qualifies(&self, game_config: &GameConfig, time: Duration) -> bool {
+        match self.tables.get(&config_name(game_config)) {
+            Some(table) => {
+                table.len() < HIGH_SCORES_PER_TABLE
+                    || table.last().is_some_and(|last| time < last.time())
+            }
+            None => true,
+        }
+    }
+
+    // Returns the rank of the new score if it made it into the table
+    pub fn insert(&mut self, game_config: &GameConfig, high_score: HighScore) -> Option<usize> {
+        self.last_name.clone_from(&high_score.name);
+        let table = self.tables.entry(config_name(game_config)).or_default();
+        let rank = table.partition_point(|score| score.time_ms <= high_score.time_ms);
+        if rank >= HIGH_SCORES_PER_TABLE {
+            return None;
+        }
+        table.insert(rank, high_score);
+        table.truncate(HIGH_SCORES_PER_TABLE);
+        Some(rank)
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn load() -> Self {
+        super::storage::load_json(HIGH_SCORES_FILE)
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn save(&self) -> std::io::Result<()> {
+        super::storage::save_json(HIGH_SCORES_FILE, self)
+    }
+
+    pub fn create_tables_model(&self) -> ModelRc<SharedString> {
+        let names: Vec<SharedString> = self.tables.keys().map(|name| name.into()).collect();
+        VecModel::from_slice(&names)
+    }
+
+    pub fn create_model(&self, table_name: &str) -> ModelRc<ModelRc<StandardListViewItem>> {
+        let mut rows = Vec::new();
+        for (rank, score) in self.tables.get(table_name).into_iter().flatten().enumerate() {
+            let cells: Vec<StandardListViewItem> = [
+                (rank + 1).to_string(),
+                score.name.clone(),
+                format_time(score.time()),
+                score.date.clone(),
+                score.three_bv.to_string(),
+                format!("{:.0}%", score.efficiency * 100.0),
+            ]
+            .into_iter()
+            .map(|cell| cell.as_str().into())
+            .collect();
+            rows.push(VecModel::from_slice(&cells));
+        }
+        VecModel::from_slice(&rows)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("difficulty,rank,name,time,date,3bv,efficiency\n");
+        for (table_name, table) in &self.tables {
+            for (rank, score) in table.iter().enumerate() {
+                csv += &format!(
+                    "{},{},{},{},{},{},{:.3}\n",
+                    csv_field(table_name),
+                    rank + 1,
+                    csv_field(&score.name),
+                    format_time(score.time()),
+                    csv_field(&score.date),
+                    score.three_bv,
+                    score.efficiency
+                );
+            }
+        }
+        csv
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,229 @@
+// Boards which are not full rectangles, tiles outside the mask are gaps
+// Plain text layout of a mask, one line per row:
+//   `X` tile in play          `.` gap
+// Empty lines and lines starting with `#` are ignored.
+
+use std::{error::Error, fmt};
+
+use super::Shape;
+
+pub const TILE: char = 'X';
+pub const GAP: char = '.';
+pub const COMMENT: char = '#';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardMask {
+    pub name: String,
+    // Shape the mask was made from, None when opened from a file
+    pub preset: Option<Shape>,
+    // One row of the board after another, true for the tiles in play
+    pub tiles: Vec<Vec<bool>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MaskError {
+    Empty,
+    RaggedRow { row: usize },
+    UnknownSymbol { row: usize, col: usize, symbol: char },
+    TooFewTiles,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskError::Empty => write!(f, "mask has no rows"),
+            MaskError::RaggedRow { row } => {
+                write!(f, "row {} has a different length than the first row", row + 1)
+            }
+            MaskError::UnknownSymbol { row, col, symbol } => write!(
+                f,
+                "unknown symbol '{}' at row {}, column {}",
+                symbol,
+                row + 1,
+                col + 1
+            ),
+            MaskError::TooFewTiles => write!(f, "mask needs at least two tiles in play"),
+        }
+    }
+}
+
+impl Error for MaskError {}
+
+impl BoardMask {
+    // Mask of a preset shape filling a board, None for the plain rectangle
+    pub fn preset(shape: Shape, row_count: usize, col_count: usize) -> Option<Self> {
+        if shape == Shape::Rectangle {
+            return None;
+        }
+        // Centres of the tiles from -1 to 1, y grows downwards
+        let inside = |row: usize, col: usize| {
+            let x = (col as f64 + 0.5) / col_count as f64 * 2.0 - 1.0;
+            let y = (row as f64 + 0.5) / row_count as f64 * 2.0 - 1.0;
+            match shape {
+                Shape::Rectangle => true,
+                Shape::Heart => {
+                    let (x, y) = (x * 1.2, 0.2 - y * 1.15);
+                    (x * x + y * y - 1.0).powi(3) - x * x * y.powi(3) <= 0.0
+                }
+                Shape::Donut => (0.4..=1.0).contains(&x.hypot(y)),
+                Shape::Diamond => x.abs() + y.abs() <= 1.0,
+            }
+        };
+        Some(Self {
+            name: format!("{shape:?}"),
+            preset: Some(shape),
+            tiles: (0..row_count)
+                .map(|row| (0..col_count).map(|col| inside(row, col)).collect())
+                .collect(),
+        })
+    }
+
+    // Same shape stretched or shrunk to another board, each tile takes the nearest one of the mask
+    pub fn resized(&self, row_count: usize, col_count: usize) -> Self {
+        let rows = self.tiles.len();
+        let cols = self.tiles.first().map_or(0, Vec::len);
+        Self {
+            name: self.name.clone(),
+            preset: self.preset,
+            tiles: (0..row_count)
+                .map(|row| {
+                    (0..col_count)
+                        .map(|col| self.tiles[row * rows / row_count][col * cols / col_count])
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    // Rows and columns, None when the rows differ in length or the mask is empty
+    pub fn size(&self) -> Option<(usize, usize)> {
+        let cols = self.tiles.first().map_or(0, Vec::len);
+        (cols > 0 && self.tiles.iter().all(|row| row.len() == cols))
+            .then_some((self.tiles.len(), cols))
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.iter().flatten().filter(|tile| **tile).count()
+    }
+
+    // Mines of a full board thinned out to the tiles in play, always leaving a safe tile
+    pub fn mine_count(&self, mine_count: usize) -> usize {
+        let tiles = self.tile_count();
+        let total = self.tiles.len() * self.tiles.first().map_or(0, Vec::len);
+        if total == 0 {
+            return 0;
+        }
+        ((mine_count * tiles + total / 2) / total).clamp(1, tiles.saturating_sub(1).max(1))
+    }
+}
+
+pub fn parse_mask(name: &str, text: &str) -> Result<BoardMask, MaskError> {
+    let mut tiles: Vec<Vec<bool>> = Vec::new();
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(COMMENT));
+    for (i, line) in lines.enumerate() {
+        let mut row_vec = Vec::new();
+        for (j, symbol) in line.chars().enumerate() {
+            match symbol {
+                TILE => row_vec.push(true),
+                GAP => row_vec.push(false),
+                _ => return Err(MaskError::UnknownSymbol { row: i, col: j, symbol }),
+            }
+        }
+        if let Some(first) = tiles.first()
+            && first.len() != row_vec.len()
+        {
+            return Err(MaskError::RaggedRow { row: i });
+        }
+        tiles.push(row_vec);
+    }
+
+    if tiles.is_empty() {
+        return Err(MaskError::Empty);
+    }
+    let mask = BoardMask {
+        name: name.into(),
+        preset: None,
+        tiles,
+    };
+    if mask.tile_count() < 2 {
+        return Err(MaskError::TooFewTiles);
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(rows: &[&str]) -> BoardMask {
+        parse_mask("test", &rows.join("\n")).unwrap()
+    }
+
+    #[test]
+    fn parse_reads_tiles_and_gaps() {
+        let mask = parse_mask("heart", "# comment\n.X.\n\nXXX\n").unwrap();
+        assert_eq!(mask.name, "heart");
+        assert_eq!(mask.preset, None);
+        assert_eq!(
+            mask.tiles,
+            [vec![false, true, false], vec![true, true, true]]
+        );
+        assert_eq!(mask.size(), Some((2, 3)));
+        assert_eq!(mask.tile_count(), 4);
+    }
+
+    #[test]
+    fn parse_refuses_broken_masks() {
+        assert_eq!(
+            parse_mask("test", "# only a comment"),
+            Err(MaskError::Empty)
+        );
+        assert_eq!(
+            parse_mask("test", "XX\nX"),
+            Err(MaskError::RaggedRow { row: 1 })
+        );
+        assert_eq!(
+            parse_mask("test", "Xo"),
+            Err(MaskError::UnknownSymbol {
+                row: 0,
+                col: 1,
+                symbol: 'o'
+            })
+        );
+        assert_eq!(parse_mask("test", "X.\n.."), Err(MaskError::TooFewTiles));
+    }
+
+    #[test]
+    fn size_needs_rows_of_one_length() {
+        let mut ragged = mask(&["XX", "X."]);
+        ragged.tiles[1].pop();
+        assert_eq!(ragged.size(), None);
+        ragged.tiles.clear();
+        assert_eq!(ragged.size(), None);
+    }
+
+    #[test]
+    fn resized_takes_the_nearest_tile() {
+        let mask = mask(&["X.", ".X"]);
+        let larger = mask.resized(4, 4);
+        assert_eq!(larger.size(), Some((4, 4)));
+        assert_eq!(larger.tiles[0], [true, true, false, false]);
+        assert_eq!(larger.tiles[3], [false, false, true, true]);
+        assert_eq!(larger.tile_count(), 8);
+        let smaller = mask.resized(1, 1);
+        assert_eq!(smaller.tiles, [vec![true]]);
+    }
+
+    #[test]
+    fn mine_count_thins_out_and_leaves_a_safe_tile() {
+        let mask = mask(&["XX..", "XX..", "XX..", "XX.."]);
+        // Half the tiles are in play, so half the mines are kept
+        assert_eq!(mask.mine_count(6), 3);
+        assert_eq!(mask.mine_count(0), 1);
+        assert_eq!(mask.mine_count(16), 7);
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use super::{
+    GameConfig, GameSummary, MINE_VALUE, Tile, format_time, grid_position, index_to_position,
+    position_to_index,
+    replay::{ReplayAction, ReplayEvent},
+    surronding_indicies, tile_at,
+};
+
+// Time added to the score of a game for every life lost in it
+pub const LIFE_PENALTY: Duration = Duration::from_secs(10);
+
+// Minimum number of clicks needed to clear a board: one per opening plus one
+// per number which is not on the border of an opening
+pub fn three_bv(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> u32 {
+    // Lying numbers have no openings, every safe tile is a click
+    if game_config.liar {
+        return tiles
+            .iter()
+            .flatten()
+            .filter(|tile| tile.value != MINE_VALUE && !tile.disabled)
+            .count() as u32;
+    }
+    let mut marked =
+        vec![false; game_config.row_count * game_config.col_count * game_config.layer_count];
+    let mut three_bv = 0;
+
+    // Openings
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let index = position_to_index(
+                game_config,
+                &grid_position(game_config, i, j),
+            );
+            if tile.value != 0 || tile.disabled || marked[index] {
+                continue;
+            }
+            three_bv += 1;
+            marked[index] = true;
+            let mut stack = vec![index];
+            while let Some(index) = stack.pop() {
+                let position = index_to_position(game_config, index);
+                for around in surronding_indicies(game_config, &position) {
+                    if marked[around] {
+                        continue;
+                    }
+                    marked[around] = true;
+                    let position = index_to_position(game_config, around);
+                    if tile_at(game_config, tiles, &position).value == 0 {
+                        stack.push(around);
+                    }
+                }
+            }
+        }
+    }
+
+    // Numbers left
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let index = position_to_index(
+                game_config,
+                &grid_position(game_config, i, j),
+            );
+            if tile.value != MINE_VALUE && !tile.disabled && !marked[index] {
+                three_bv += 1;
+            }
+        }
+    }
+    three_bv
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameMetrics {
+    pub three_bv: u32,
+    pub left_clicks: u32,
+    pub right_clicks: u32,
+    pub chord_clicks: u32,
+    pub time_ms: u64,
+    pub lives_lost: u32,
+}
+
+impl GameMetrics {
+    // Every recorded action is a click
+    pub fn new(
+        game_config: &GameConfig,
+        tiles: &[Vec<Tile>],
+        events: &[ReplayEvent],
+        time: Duration,
+    ) -> Self {
+        let mut metrics = Self {
+            three_bv: three_bv(game_config, tiles),
+            time_ms: time.as_millis() as u64,
+            lives_lost: tiles.iter().flatten().filter(|tile| tile.exploded).count() as u32,
+            ..Default::default()
+        };
+        for event in events {
+            match event.action {
+                ReplayAction::Reveal => metrics.left_clicks += 1,
+                ReplayAction::Flag | ReplayAction::Unflag => metrics.right_clicks += 1,
+                ReplayAction::Chord => metrics.chord_clicks += 1,
+            }
+        }
+        metrics
+    }
+
+    pub fn clicks(&self) -> u32 {
+        self.left_clicks + self.right_clicks + self.chord_clicks
+    }
+
+    pub fn time(&self) -> Duration {
+        Duration::from_millis(self.time_ms)
+    }
+
+    // Time ranked in the high scores, lost lives make it longer
+    pub fn score_time(&self) -> Duration {
+        self.time() + LIFE_PENALTY * self.lives_lost
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.time_ms == 0 {
+            0.0
+        } else {
+            self.three_bv as f64 / self.time().as_secs_f64()
+        }
+    }
+
+    // Index of efficiency, 3BV per click
+    pub fn ioe(&self) -> f64 {
+        if self.clicks() == 0 {
+            0.0
+        } else {
+            self.three_bv as f64 / self.clicks() as f64
+        }
+    }
+
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            time: format_time(self.time()).into(),
+            three_bv: self.three_bv as i32,
+            three_bv_per_second: format!("{:.2}", self.three_bv_per_second()).into(),
+            ioe: format!("{:.2}", self.ioe()).into(),
+            clicks: self.clicks() as i32,
+            left_clicks: self.left_clicks as i32,
+            right_clicks: self.right_clicks as i32,
+            chord_clicks: self.chord_clicks as i32,
+            lives_lost: self.lives_lost as i32,
+            score_time: format_time(self.score_time()).into(),
+        }
+    }
+}
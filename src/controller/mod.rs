@@ -1,70 +1,258 @@
 slint::include_modules!();
 
-use meta_enum::{MetaEnum, ParseMetaEnumError};
-use rand::{self, seq::index::sample_weighted};
-use slint::{Model as _, ModelRc, SharedString, VecModel};
+use std::time::Duration;
+
+use rand::{self, Rng, seq::index::sample_weighted};
+use slint::{Model as _, ModelRc, VecModel};
+
+use mask::BoardMask;
+pub use options::{FirstClick, GameDifficulty, GameMode, Neighbourhood, Shape, Topology};
+
+mod options;
+
+pub mod board_text;
+pub mod clock;
+pub mod endless;
+pub mod high_scores;
+pub mod mask;
+pub mod metrics;
+pub mod replay;
+pub mod rmv;
+pub mod settings;
+#[cfg(feature = "persistence")]
+pub mod skin;
+pub mod solver;
+pub mod statistics;
+#[cfg(feature = "persistence")]
+pub mod storage;
 
 pub const MINE_VALUE: i32 = -1;
 
-#[derive(Debug, Clone)]
+// Bumped whenever a serialized type changes its shape
+// 2: Position has a layer
+// 3: Tile counts its mines and flags
+// 4: Tile can be a gap
+// 5: Tile can have exploded
+pub const FORMAT_VERSION: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameConfig {
     pub row_count: usize,
     pub col_count: usize,
     pub mine_count: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub topology: Topology,
+    // Edges wrap around so the board has no border
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub wrap: bool,
+    // Layers of row_count x col_count tiles, kept one after another in the grid
+    #[cfg_attr(feature = "serde", serde(default = "default_layer_count"))]
+    pub layer_count: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub neighbourhood: Neighbourhood,
+    // Mines a single tile can hold, numbers add up the mines of every tile around them
+    #[cfg_attr(feature = "serde", serde(default = "default_max_tile_mines"))]
+    pub max_tile_mines: usize,
+    // Tiles of every layer which are in play, the others are gaps
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mask: Option<BoardMask>,
+    // Every number is one more or one less than the mines around it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub liar: bool,
+    // Mines which can be hit before the game is lost
+    #[cfg_attr(feature = "serde", serde(default = "default_lives"))]
+    pub lives: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: GameMode,
+    // Only used by the timed modes
+    #[cfg_attr(feature = "serde", serde(default = "default_time_limit_secs"))]
+    pub time_limit_secs: u64,
 }
 
-#[derive(Debug, Clone, Copy, MetaEnum)]
-pub enum GameDifficulty {
-    Easy,
-    Medium,
-    Hard,
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            row_count: 0,
+            col_count: 0,
+            mine_count: 0,
+            topology: Topology::default(),
+            wrap: false,
+            layer_count: default_layer_count(),
+            neighbourhood: Neighbourhood::default(),
+            max_tile_mines: default_max_tile_mines(),
+            mask: None,
+            liar: false,
+            lives: default_lives(),
+            mode: GameMode::default(),
+            time_limit_secs: default_time_limit_secs(),
+        }
+    }
 }
 
-impl GameDifficulty {
-    pub fn create_model() -> ModelRc<SharedString> {
-        let model: Vec<_> = GameDifficulty::keys()
-            .into_iter()
-            .map(|key| key.into())
-            .collect();
-        VecModel::from_slice(&model)
-    }
+fn default_layer_count() -> usize {
+    1
+}
+
+fn default_max_tile_mines() -> usize {
+    1
+}
+
+fn default_lives() -> usize {
+    1
+}
+
+fn default_time_limit_secs() -> u64 {
+    180
 }
 
 impl GameConfig {
+    // Square flat board with the usual neighbours, the only kind board and rmv files hold
+    pub fn is_classic(&self) -> bool {
+        self.topology == Topology::Square
+            && !self.wrap
+            && self.layer_count == 1
+            && self.neighbourhood == Neighbourhood::Classic
+            && self.max_tile_mines == 1
+            && self.mask.is_none()
+            && !self.liar
+            && self.lives == 1
+            && self.mode != GameMode::Endless
+    }
+
+    // Room for every mine on the tiles in play with a tile left for a safe first click
+    pub fn fits_mines(&self) -> bool {
+        let layer_tiles = match &self.mask {
+            Some(mask) => mask.tile_count(),
+            None => self.row_count * self.col_count,
+        };
+        let tile_count = layer_tiles * self.layer_count;
+        tile_count >= 2 && self.mine_count <= (tile_count - 1) * self.max_tile_mines.max(1)
+    }
+
     pub fn new(difficulty: GameDifficulty) -> Self {
         match difficulty {
             GameDifficulty::Easy => Self {
                 row_count: 8,
                 col_count: 8,
                 mine_count: 10,
+                ..Default::default()
             },
             GameDifficulty::Medium => Self {
                 row_count: 16,
                 col_count: 16,
                 mine_count: 40,
+                ..Default::default()
             },
             GameDifficulty::Hard => Self {
                 row_count: 16,
                 col_count: 30,
                 mine_count: 99,
+                ..Default::default()
             },
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameOver;
 
+// Snapshot of a whole game which tools can persist and exchange
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedGame {
+    pub format_version: u32,
+    pub game_config: GameConfig,
+    pub state: GameState,
+    pub tiles: Vec<Vec<Tile>>,
+}
+
+impl SavedGame {
+    pub fn new(game_config: &GameConfig, state: GameState, tiles: &[Vec<Tile>]) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            game_config: game_config.clone(),
+            state,
+            tiles: tiles.to_vec(),
+        }
+    }
+
+    // Tiles change shape between versions, so only saves of the current one can be read
+    pub fn is_supported(format_version: u32) -> bool {
+        format_version == FORMAT_VERSION
+    }
+
+    // The version is read first so an old save fails on it rather than on a tile serde can not read
+    #[cfg(feature = "serde")]
+    pub fn from_json(text: &str) -> Result<Self, SavedGameError> {
+        #[derive(serde::Deserialize)]
+        struct Version {
+            format_version: u32,
+        }
+        let version: Version = serde_json::from_str(text)
+            .map_err(|err| SavedGameError::Invalid(err.to_string()))?;
+        if !Self::is_supported(version.format_version) {
+            return Err(SavedGameError::UnsupportedVersion(version.format_version));
+        }
+        let saved: Self = serde_json::from_str(text)
+            .map_err(|err| SavedGameError::Invalid(err.to_string()))?;
+        // Gaps are looked up in the mask for every tile of the board
+        let game_config = &saved.game_config;
+        if let Some(mask) = &game_config.mask
+            && mask.size() != Some((game_config.row_count, game_config.col_count))
+        {
+            return Err(SavedGameError::Invalid(
+                "shape does not match the board".into(),
+            ));
+        }
+        if !game_config.fits_mines() {
+            return Err(SavedGameError::Invalid(
+                "board has no room for its mines".into(),
+            ));
+        }
+        Ok(saved)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, SavedGameError> {
+        serde_json::to_string_pretty(self).map_err(|err| SavedGameError::Invalid(err.to_string()))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SavedGameError {
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl std::fmt::Display for SavedGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SavedGameError::UnsupportedVersion(version) => write!(
+                f,
+                "saved game has format version {version}, this version reads {FORMAT_VERSION}"
+            ),
+            SavedGameError::Invalid(err) => write!(f, "saved game can not be read: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SavedGameError {}
+
 pub fn new_grid(game_config: &GameConfig) -> Vec<Vec<Tile>> {
     let mut tiles = Vec::new();
 
-    for _ in 0..game_config.row_count {
+    for i in 0..game_config.row_count * game_config.layer_count {
         let mut row_vec = Vec::new();
-        for _ in 0..game_config.col_count {
+        for j in 0..game_config.col_count {
             row_vec.push(Tile {
                 value: 0,
                 visible: false,
-                flagged: false,
+                mines: 0,
+                flags: 0,
+                disabled: !in_play(game_config, &grid_position(game_config, i, j)),
+                exploded: false,
             });
         }
         tiles.push(row_vec);
@@ -72,22 +260,60 @@ pub fn new_grid(game_config: &GameConfig) -> Vec<Vec<Tile>> {
     tiles
 }
 
-pub fn clear_grid(tiles: &mut Vec<Vec<Tile>>) {
+pub fn clear_grid(tiles: &mut [Vec<Tile>]) {
     for row in tiles.iter_mut() {
         for tile in row {
-            tile.flagged = false;
+            tile.flags = 0;
             tile.visible = false;
             tile.value = 0;
+            tile.mines = 0;
+            tile.exploded = false;
         }
     }
 }
 
-pub fn fill_grid(game_config: &GameConfig, first_move: Position, tiles: &mut Vec<Vec<Tile>>) {
-    // Making First Button not be a bomb
-    let mut zero_weights = surronding_indicies(game_config, &first_move);
-    zero_weights.push(position_to_index(game_config, &first_move));
-    let weight = |index| {
-        if zero_weights.contains(&index) {
+pub fn fill_grid(game_config: &GameConfig, first_move: Position, tiles: &mut [Vec<Tile>]) {
+    place_mines(game_config, &first_move, FirstClick::Opening, tiles);
+
+    // Showing clicked Button and Around
+    change_visibility(game_config, tiles, &first_move, true);
+    let lost = expand_selection(game_config, &first_move, tiles);
+    assert_eq!(lost, None);
+}
+
+pub fn place_mines(
+    game_config: &GameConfig,
+    first_move: &Position,
+    first_click: FirstClick,
+    tiles: &mut [Vec<Tile>],
+) {
+    // Making First Button not be a bomb, boards too full for the rule fall back to a weaker one
+    let length = game_config.row_count * game_config.col_count * game_config.layer_count;
+    let max_tile_mines = game_config.max_tile_mines.max(1);
+    let gaps: Vec<usize> = (0..length)
+        .filter(|index| !in_play(game_config, &index_to_position(game_config, *index)))
+        .collect();
+    let tile_count = length - gaps.len();
+    let mut zero_weights = Vec::new();
+    if first_click == FirstClick::Opening {
+        zero_weights = surronding_indicies(game_config, first_move);
+    }
+    if first_click != FirstClick::Anywhere {
+        zero_weights.push(position_to_index(game_config, first_move));
+    }
+    if (tile_count - zero_weights.len()) * max_tile_mines < game_config.mine_count {
+        zero_weights.retain(|index| *index == position_to_index(game_config, first_move));
+    }
+    if (tile_count - zero_weights.len()) * max_tile_mines < game_config.mine_count {
+        zero_weights.clear();
+    }
+    let mut excluded = vec![false; length];
+    for index in zero_weights.into_iter().chain(gaps) {
+        excluded[index] = true;
+    }
+    // Every tile has a slot for each mine it can hold, gaps have none
+    let weight = |slot| {
+        if excluded[slot / max_tile_mines] {
             0.0
         } else {
             0.5
@@ -96,57 +322,106 @@ pub fn fill_grid(game_config: &GameConfig, first_move: Position, tiles: &mut Vec
 
     // Getting the random bombs
     let mut rng = rand::rng();
-    let length = (game_config.row_count * game_config.col_count) as usize;
-    let amount = game_config.mine_count as usize;
-    let bombs_index = sample_weighted(&mut rng, length, weight, amount)
+    let amount = game_config.mine_count;
+    let bombs_slot = sample_weighted(&mut rng, length * max_tile_mines, weight, amount)
         .unwrap()
         .into_vec();
+    let mut mines = vec![0; length];
+    for slot in bombs_slot {
+        mines[slot / max_tile_mines] += 1;
+    }
 
     // Setting The Bombs on the Grid
     for (i, row) in tiles.iter_mut().enumerate() {
         for (j, tile) in row.iter_mut().enumerate() {
-            let value = match bombs_index.iter().find(|b| {
-                **b == position_to_index(
-                    game_config,
-                    &Position {
-                        row: i as i32,
-                        col: j as i32,
-                    },
-                )
-            }) {
-                Some(_) => MINE_VALUE,
-                None => 0,
-            };
-            tile.value = value;
+            tile.mines = mines[position_to_index(game_config, &grid_position(game_config, i, j))];
+            tile.value = if tile.mines > 0 { MINE_VALUE } else { 0 };
         }
     }
 
     // Setting The Numbers
+    fill_numbers(game_config, tiles);
+}
+
+pub fn fill_numbers(game_config: &GameConfig, tiles: &mut [Vec<Tile>]) {
+    let mut bombs = vec![0; game_config.row_count * game_config.col_count * game_config.layer_count];
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if tile.value == MINE_VALUE {
+                bombs[position_to_index(game_config, &grid_position(game_config, i, j))] =
+                    tile.mines;
+            }
+        }
+    }
+
+    let mut rng = rand::rng();
     for (i, row) in tiles.iter_mut().enumerate() {
         for (j, tile) in row.iter_mut().enumerate() {
-            if tile.value != MINE_VALUE {
-                let mut bombs = 0;
+            if tile.value != MINE_VALUE && !tile.disabled {
                 let around = surronding_indicies(
                     game_config,
-                    &Position {
-                        row: i as i32,
-                        col: j as i32,
-                    },
+                    &grid_position(game_config, i, j),
                 );
-                for index in around {
-                    if bombs_index.contains(&index) {
-                        bombs += 1;
-                    }
-                }
-                tile.value = bombs;
+                let value: i32 = around.iter().map(|index| bombs[*index]).sum();
+                // A lie can not go below zero, so no mines around always shows 1
+                tile.value = if !game_config.liar {
+                    value
+                } else if value == 0 || rng.random_bool(0.5) {
+                    value + 1
+                } else {
+                    value - 1
+                };
             }
         }
     }
+}
 
-    // Showing clicked Button and Around
-    tiles[first_move.row as usize][first_move.col as usize].visible = true;
-    let lost = expand_selection(game_config, &first_move, tiles);
-    assert_eq!(lost, None);
+pub fn remaining_flags(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> i32 {
+    let flags: i32 = tiles.iter().flatten().map(marked_mines).sum();
+    game_config.mine_count as i32 - flags
+}
+
+// Mines of a tile the player knows of, flagged or exploded
+pub fn marked_mines(tile: &Tile) -> i32 {
+    if tile.exploded { tile.mines } else { tile.flags }
+}
+
+pub fn lives_left(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> i32 {
+    let lost = tiles.iter().flatten().filter(|tile| tile.exploded).count();
+    game_config.lives as i32 - lost as i32
+}
+
+// A revealed mine explodes and costs a life, the game is over with the last one
+#[must_use]
+pub fn mine_hit(
+    game_config: &GameConfig,
+    tiles: &mut [Vec<Tile>],
+    position: &Position,
+) -> Option<GameOver> {
+    tiles[grid_row(game_config, position)][position.col as usize].exploded = true;
+    (lives_left(game_config, tiles) <= 0).then_some(GameOver)
+}
+
+// Deduce the state of a grid which did not come from fill_grid, e.g. a loaded board
+pub fn board_state(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> GameState {
+    let mut any_visible = false;
+    for row in tiles {
+        for tile in row {
+            if tile.visible && tile.value == MINE_VALUE && !tile.exploded {
+                return GameState::Lose;
+            }
+            any_visible |= tile.visible;
+        }
+    }
+    if lives_left(game_config, tiles) <= 0 {
+        GameState::Lose
+    } else if check_win(game_config, tiles) {
+        GameState::Win
+    } else if any_visible {
+        GameState::Normal
+    } else {
+        GameState::Initial
+    }
 }
 
 pub fn model_grid_to_vec2d<T>(model: ModelRc<ModelRc<T>>) -> Vec<Vec<T>> {
@@ -160,10 +435,10 @@ pub fn model_grid_to_vec2d<T>(model: ModelRc<ModelRc<T>>) -> Vec<Vec<T>> {
     tiles_vec
 }
 
-pub fn vec2d_to_model_grid(tiles: &Vec<Vec<Tile>>) -> ModelRc<ModelRc<Tile>> {
+pub fn vec2d_to_model_grid(tiles: &[Vec<Tile>]) -> ModelRc<ModelRc<Tile>> {
     let mut grid_model = Vec::new();
     for row in tiles {
-        grid_model.push(VecModel::from_slice(&row));
+        grid_model.push(VecModel::from_slice(row));
     }
     VecModel::from_slice(&grid_model)
 }
@@ -172,19 +447,18 @@ pub fn vec2d_to_model_grid(tiles: &Vec<Vec<Tile>>) -> ModelRc<ModelRc<Tile>> {
 pub fn expand_selection(
     game_config: &GameConfig,
     position: &Position,
-    tiles: &mut Vec<Vec<Tile>>,
+    tiles: &mut [Vec<Tile>],
 ) -> Option<GameOver> {
-    if tiles[position.row as usize][position.col as usize].value == 0 {
+    // A lying 0 has a mine around it, so only chords open the tiles around lying numbers
+    if !game_config.liar && tile_at(game_config, tiles, position).value == 0 {
         let around = surronding_indicies(game_config, position);
         for index in around {
             let pos = index_to_position(game_config, index);
-            let tile = &mut tiles[pos.row as usize][pos.col as usize];
-            if !tile.flagged && !tile.visible {
+            let tile = &mut tiles[grid_row(game_config, &pos)][pos.col as usize];
+            if tile.flags == 0 && !tile.visible {
                 tile.visible = true;
-                if tile.value == 0 {
-                    if let Some(_) = expand_selection(game_config, &pos, tiles) {
-                        return Some(GameOver);
-                    }
+                if tile.value == 0 && expand_selection(game_config, &pos, tiles).is_some() {
+                    return Some(GameOver);
                 }
             }
         }
@@ -193,48 +467,108 @@ pub fn expand_selection(
         let mut flags = 0;
         for index in around.iter() {
             let pos = index_to_position(game_config, *index);
-            let tile = &tiles[pos.row as usize][pos.col as usize];
-            if tile.flagged {
-                flags += 1
-            }
+            flags += marked_mines(tile_at(game_config, tiles, &pos));
         }
-        let tile = &tiles[position.row as usize][position.col as usize];
-        if flags == tile.value {
+        let value = tile_at(game_config, tiles, position).value;
+        // A lying number is one off the mines around it, a chord needs at least one flag
+        let chord = if game_config.liar {
+            flags > 0 && (flags == value - 1 || flags == value + 1)
+        } else {
+            flags == value
+        };
+        if chord {
             for index in around.iter() {
                 let pos = index_to_position(game_config, *index);
-                let tile = &mut tiles[pos.row as usize][pos.col as usize];
-                if !tile.flagged && !tile.visible {
+                let tile = &mut tiles[grid_row(game_config, &pos)][pos.col as usize];
+                if tile.flags == 0 && !tile.visible {
                     tile.visible = true;
                     if tile.value == MINE_VALUE {
-                        return Some(GameOver);
-                    }
-                    if let Some(_) = expand_selection(game_config, &pos, tiles) {
+                        if mine_hit(game_config, tiles, &pos).is_some() {
+                            return Some(GameOver);
+                        }
+                    } else if expand_selection(game_config, &pos, tiles).is_some() {
                         return Some(GameOver);
                     }
                 }
             }
         }
     }
-    return None;
+    None
 }
 
+// Flags placed on a tile, at most one for each mine it can hold
 #[inline]
-pub fn change_flag(tiles: &mut Vec<Vec<Tile>>, position: &Position, flag: bool) {
-    tiles[position.row as usize][position.col as usize].flagged = flag;
+pub fn change_flag(
+    game_config: &GameConfig,
+    tiles: &mut [Vec<Tile>],
+    position: &Position,
+    flags: i32,
+) {
+    tiles[grid_row(game_config, position)][position.col as usize].flags = flags;
 }
 
 #[inline]
-pub fn change_visibility(tiles: &mut Vec<Vec<Tile>>, position: &Position, visible: bool) {
-    tiles[position.row as usize][position.col as usize].visible = visible;
+pub fn change_visibility(
+    game_config: &GameConfig,
+    tiles: &mut [Vec<Tile>],
+    position: &Position,
+    visible: bool,
+) {
+    tiles[grid_row(game_config, position)][position.col as usize].visible = visible;
+}
+
+#[inline]
+pub fn tile_at<'a>(
+    game_config: &GameConfig,
+    tiles: &'a [Vec<Tile>],
+    position: &Position,
+) -> &'a Tile {
+    &tiles[grid_row(game_config, position)][position.col as usize]
+}
+
+// Rows of one layer of the grid
+pub fn layer_rows<'a>(
+    game_config: &GameConfig,
+    tiles: &'a [Vec<Tile>],
+    layer: usize,
+) -> &'a [Vec<Tile>] {
+    let start = (layer * game_config.row_count).min(tiles.len());
+    let end = (start + game_config.row_count).min(tiles.len());
+    &tiles[start..end]
+}
+
+// Layers are kept one after another, so a position is found in the grid by its layer and row
+#[inline]
+pub fn grid_row(game_config: &GameConfig, position: &Position) -> usize {
+    position.layer as usize * game_config.row_count + position.row as usize
+}
+
+// Position of a tile found by its row and column in the grid
+pub fn grid_position(game_config: &GameConfig, i: usize, j: usize) -> Position {
+    Position {
+        layer: (i / game_config.row_count) as i32,
+        row: (i % game_config.row_count) as i32,
+        col: j as i32,
+    }
 }
 
-pub fn check_win(game_config: &GameConfig, tiles: &Vec<Vec<Tile>>) -> bool {
+// Tiles holding several mines need a flag for each of them, gaps are never revealed
+// and exploded mines count as found
+pub fn check_win(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> bool {
     let mut flags = 0;
     for row in tiles {
         for tile in row {
-            if tile.flagged {
-                flags += 1;
-            } else if !tile.flagged && !tile.visible {
+            if tile.disabled {
+                continue;
+            }
+            if tile.exploded {
+                flags += tile.mines;
+            } else if tile.flags > 0 {
+                if tile.flags != tile.mines {
+                    return false;
+                }
+                flags += tile.flags;
+            } else if !tile.visible {
                 return false;
             }
         }
@@ -244,13 +578,76 @@ pub fn check_win(game_config: &GameConfig, tiles: &Vec<Vec<Tile>>) -> bool {
 
 #[inline]
 fn position_to_index(game_config: &GameConfig, position: &Position) -> usize {
-    position.row as usize * game_config.col_count + position.col as usize
+    grid_row(game_config, position) * game_config.col_count + position.col as usize
 }
 
 fn index_to_position(game_config: &GameConfig, index: usize) -> Position {
-    let row = (index / game_config.col_count) as i32;
-    let col = (index % game_config.col_count) as i32;
-    Position { row, col }
+    grid_position(
+        game_config,
+        index / game_config.col_count,
+        index % game_config.col_count,
+    )
+}
+
+// Row and column steps to the neighbours of a position
+fn neighbour_offsets(game_config: &GameConfig, position: &Position) -> Vec<(i32, i32)> {
+    match (game_config.topology, game_config.neighbourhood) {
+        (Topology::Square, Neighbourhood::Classic) => vec![
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ],
+        (Topology::Square, Neighbourhood::Cross) => vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+        (Topology::Square, Neighbourhood::Knight) => vec![
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ],
+        (Topology::Square, Neighbourhood::Radius2) => (-2..=2)
+            .flat_map(|rows| (-2..=2).map(move |cols| (rows, cols)))
+            .filter(|offset| *offset != (0, 0))
+            .collect(),
+        // Odd rows are shifted right, so their diagonal neighbours are one column further
+        (Topology::Hexagonal, _) => {
+            let shift = position.row.rem_euclid(2);
+            vec![
+                (-1, shift - 1),
+                (-1, shift),
+                (0, -1),
+                (0, 1),
+                (1, shift - 1),
+                (1, shift),
+            ]
+        }
+    }
+}
+
+// Tiles of a shaped board outside its mask are gaps
+pub fn in_play(game_config: &GameConfig, position: &Position) -> bool {
+    game_config
+        .mask
+        .as_ref()
+        .is_none_or(|mask| mask.tiles[position.row as usize][position.col as usize])
+}
+
+// Most tiles a number can count, the largest number of a board
+pub fn max_neighbours(game_config: &GameConfig) -> usize {
+    let planar = neighbour_offsets(game_config, &Position::default()).len();
+    if game_config.layer_count > 1 {
+        planar * 3 + 2
+    } else {
+        planar
+    }
 }
 
 fn surronding_indicies(game_config: &GameConfig, position: &Position) -> Vec<usize> {
@@ -258,144 +655,502 @@ fn surronding_indicies(game_config: &GameConfig, position: &Position) -> Vec<usi
     assert!(game_config.row_count > 1);
     assert!(game_config.col_count > 1);
 
+    let row_count = game_config.row_count as i32;
+    let col_count = game_config.col_count as i32;
+    // Hexagonal rows alternate, an odd number of them can only wrap sideways
+    let wrap_rows =
+        game_config.wrap && (game_config.topology != Topology::Hexagonal || row_count % 2 == 0);
+    // Layers above and below hold the tile itself and its neighbours
+    let mut offsets: Vec<(i32, i32, i32)> = neighbour_offsets(game_config, position)
+        .into_iter()
+        .map(|(rows, cols)| (0, rows, cols))
+        .collect();
+    if game_config.layer_count > 1 {
+        for layers in [-1, 1] {
+            offsets.push((layers, 0, 0));
+            for (rows, cols) in neighbour_offsets(game_config, position) {
+                offsets.push((layers, rows, cols));
+            }
+        }
+    }
     let mut indicies = Vec::new();
+    for (layers, rows, cols) in offsets {
+        let mut around = Position {
+            layer: position.layer + layers,
+            row: position.row + rows,
+            col: position.col + cols,
+        };
+        if !(0..game_config.layer_count as i32).contains(&around.layer) {
+            continue;
+        }
+        if wrap_rows {
+            around.row = around.row.rem_euclid(row_count);
+        }
+        if game_config.wrap {
+            around.col = around.col.rem_euclid(col_count);
+        }
+        if !(0..row_count).contains(&around.row)
+            || !(0..col_count).contains(&around.col)
+            || !in_play(game_config, &around)
+        {
+            continue;
+        }
+        // Small wrapped boards reach the same tile from two sides or the tile itself
+        let index = position_to_index(game_config, &around);
+        if around != *position && !indicies.contains(&index) {
+            indicies.push(index);
+        }
+    }
+    indicies
+}
 
-    if position.row > 0 {
-        // We are not top row
-        // Above
-        indicies.push(position_to_index(
-            game_config,
-            &Position {
-                row: position.row - 1,
-                col: position.col,
-            },
-        ));
-
-        if position.col > 0 {
-            // Above Left
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row - 1,
-                    col: position.col - 1,
-                },
-            ));
+pub fn zero_pad(number: i32, length: i32) -> String {
+    let mut value = number.to_string();
+    let diff = length - value.len() as i32;
+    if diff > 0 {
+        value = "0".repeat(diff as usize) + value.as_str();
+    }
+    value
+}
 
-            // Left
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row,
-                    col: position.col - 1,
-                },
-            ));
+// Seconds truncated to hundredths
+pub fn format_time(time: Duration) -> String {
+    let hundredths = time.as_millis() / 10;
+    format!("{}.{:02}", hundredths / 100, hundredths % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neighbours(game_config: &GameConfig, row: i32, col: i32) -> Vec<(i32, i32)> {
+        layer_neighbours(game_config, 0, row, col)
+            .into_iter()
+            .map(|(_, row, col)| (row, col))
+            .collect()
+    }
+
+    fn layer_neighbours(
+        game_config: &GameConfig,
+        layer: i32,
+        row: i32,
+        col: i32,
+    ) -> Vec<(i32, i32, i32)> {
+        let position = Position { layer, row, col };
+        let mut neighbours: Vec<_> = surronding_indicies(game_config, &position)
+            .into_iter()
+            .map(|index| index_to_position(game_config, index))
+            .map(|position| (position.layer, position.row, position.col))
+            .collect();
+        neighbours.sort();
+        neighbours
+    }
+
+    fn board(row_count: usize, col_count: usize, topology: Topology, wrap: bool) -> GameConfig {
+        GameConfig {
+            row_count,
+            col_count,
+            mine_count: 1,
+            topology,
+            wrap,
+            ..Default::default()
         }
+    }
 
-        if position.col < game_config.col_count as i32 - 1 {
-            // Above Right
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row - 1,
-                    col: position.col + 1,
-                },
-            ));
+    fn hexagonal(row_count: usize, col_count: usize) -> GameConfig {
+        board(row_count, col_count, Topology::Hexagonal, false)
+    }
 
-            // Right
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row,
-                    col: position.col + 1,
-                },
-            ));
+    #[test]
+    fn hexagonal_tiles_have_six_neighbours_inside() {
+        let game_config = hexagonal(4, 4);
+        // Even rows reach one column back on the rows around, odd rows one column forward
+        assert_eq!(
+            neighbours(&game_config, 2, 1),
+            [(1, 0), (1, 1), (2, 0), (2, 2), (3, 0), (3, 1)]
+        );
+        assert_eq!(
+            neighbours(&game_config, 1, 1),
+            [(0, 1), (0, 2), (1, 0), (1, 2), (2, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn hexagonal_corners_and_edges_drop_neighbours_off_the_board() {
+        let game_config = hexagonal(4, 4);
+        assert_eq!(neighbours(&game_config, 0, 0), [(0, 1), (1, 0)]);
+        assert_eq!(neighbours(&game_config, 1, 3), [(0, 3), (1, 2), (2, 3)]);
+        assert_eq!(neighbours(&game_config, 3, 0), [(2, 0), (2, 1), (3, 1)]);
+        assert_eq!(
+            neighbours(&game_config, 0, 2),
+            [(0, 1), (0, 3), (1, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn wrapped_corners_reach_the_opposite_edges() {
+        let game_config = board(4, 4, Topology::Square, true);
+        assert_eq!(
+            neighbours(&game_config, 0, 0),
+            [
+                (0, 1),
+                (0, 3),
+                (1, 0),
+                (1, 1),
+                (1, 3),
+                (3, 0),
+                (3, 1),
+                (3, 3)
+            ]
+        );
+        // Every other tile of a 2x2 board once, never the tile itself
+        let game_config = board(2, 2, Topology::Square, true);
+        assert_eq!(neighbours(&game_config, 0, 0), [(0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn wrapped_hexagonal_rows_keep_their_shift() {
+        let game_config = board(4, 4, Topology::Hexagonal, true);
+        assert_eq!(
+            neighbours(&game_config, 0, 0),
+            [(0, 1), (0, 3), (1, 0), (1, 3), (3, 0), (3, 3)]
+        );
+        assert_eq!(
+            neighbours(&game_config, 1, 3),
+            [(0, 0), (0, 3), (1, 0), (1, 2), (2, 0), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn odd_hexagonal_boards_only_wrap_sideways() {
+        let game_config = board(3, 4, Topology::Hexagonal, true);
+        assert_eq!(
+            neighbours(&game_config, 0, 0),
+            [(0, 1), (0, 3), (1, 0), (1, 3)]
+        );
+        assert_eq!(
+            neighbours(&game_config, 2, 1),
+            [(1, 0), (1, 1), (2, 0), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn layers_add_the_tile_and_its_neighbours_above_and_below() {
+        let game_config = GameConfig {
+            layer_count: 3,
+            ..board(3, 3, Topology::Square, false)
+        };
+        assert_eq!(layer_neighbours(&game_config, 1, 1, 1).len(), 26);
+        // The first and last layers only have one layer next to them
+        assert_eq!(
+            layer_neighbours(&game_config, 0, 0, 0),
+            [
+                (0, 0, 1),
+                (0, 1, 0),
+                (0, 1, 1),
+                (1, 0, 0),
+                (1, 0, 1),
+                (1, 1, 0),
+                (1, 1, 1)
+            ]
+        );
+        assert_eq!(
+            layer_neighbours(&game_config, 2, 2, 2),
+            [
+                (1, 1, 1),
+                (1, 1, 2),
+                (1, 2, 1),
+                (1, 2, 2),
+                (2, 1, 1),
+                (2, 1, 2),
+                (2, 2, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn layered_hexagonal_rows_keep_their_shift() {
+        let game_config = GameConfig {
+            layer_count: 2,
+            ..board(4, 4, Topology::Hexagonal, true)
+        };
+        assert_eq!(
+            layer_neighbours(&game_config, 1, 1, 3),
+            [
+                (0, 0, 0),
+                (0, 0, 3),
+                (0, 1, 0),
+                (0, 1, 2),
+                (0, 1, 3),
+                (0, 2, 0),
+                (0, 2, 3),
+                (1, 0, 0),
+                (1, 0, 3),
+                (1, 1, 0),
+                (1, 1, 2),
+                (1, 2, 0),
+                (1, 2, 3)
+            ]
+        );
+    }
+
+    fn neighbourhood(topology: Topology, neighbourhood: Neighbourhood) -> GameConfig {
+        GameConfig {
+            neighbourhood,
+            ..board(5, 5, topology, false)
         }
+    }
 
-        if position.row < game_config.row_count as i32 - 1 {
-            // Below
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row + 1,
-                    col: position.col,
-                },
-            ));
+    #[test]
+    fn every_neighbourhood_has_its_own_offsets() {
+        let counts = [
+            (Neighbourhood::Classic, 8),
+            (Neighbourhood::Cross, 4),
+            (Neighbourhood::Knight, 8),
+            (Neighbourhood::Radius2, 24),
+        ];
+        for (rule, count) in counts {
+            let game_config = neighbourhood(Topology::Square, rule);
+            let offsets = neighbour_offsets(&game_config, &Position::default());
+            assert_eq!(offsets.len(), count);
+            assert!(!offsets.contains(&(0, 0)));
+            assert_eq!(max_neighbours(&game_config), count);
+        }
+        let game_config = neighbourhood(Topology::Square, Neighbourhood::Cross);
+        assert_eq!(
+            neighbours(&game_config, 2, 2),
+            [(1, 2), (2, 1), (2, 3), (3, 2)]
+        );
+        let game_config = neighbourhood(Topology::Square, Neighbourhood::Knight);
+        assert_eq!(
+            neighbours(&game_config, 2, 2),
+            [
+                (0, 1),
+                (0, 3),
+                (1, 0),
+                (1, 4),
+                (3, 0),
+                (3, 4),
+                (4, 1),
+                (4, 3)
+            ]
+        );
+        assert_eq!(neighbours(&game_config, 0, 0), [(1, 2), (2, 1)]);
+        let game_config = neighbourhood(Topology::Square, Neighbourhood::Radius2);
+        assert_eq!(neighbours(&game_config, 0, 0).len(), 8);
+    }
 
-            if position.col > 0 {
-                // Below Left
-                indicies.push(position_to_index(
-                    game_config,
-                    &Position {
-                        row: position.row + 1,
-                        col: position.col - 1,
-                    },
-                ));
+    #[test]
+    fn hexagonal_tiles_ignore_the_neighbourhood() {
+        let classic = neighbourhood(Topology::Hexagonal, Neighbourhood::Classic);
+        for rule in [
+            Neighbourhood::Cross,
+            Neighbourhood::Knight,
+            Neighbourhood::Radius2,
+        ] {
+            let game_config = neighbourhood(Topology::Hexagonal, rule);
+            for position in [
+                Position::default(),
+                Position {
+                    layer: 0,
+                    row: 1,
+                    col: 0,
+                },
+            ] {
+                assert_eq!(
+                    neighbour_offsets(&game_config, &position),
+                    neighbour_offsets(&classic, &position)
+                );
             }
+        }
+    }
 
-            if position.col < game_config.col_count as i32 - 1 {
-                // Below Right
-                indicies.push(position_to_index(
-                    game_config,
-                    &Position {
-                        row: position.row + 1,
-                        col: position.col + 1,
-                    },
-                ));
+    fn stacked(
+        row_count: usize,
+        col_count: usize,
+        mine_count: usize,
+        max_tile_mines: usize,
+    ) -> GameConfig {
+        GameConfig {
+            mine_count,
+            max_tile_mines,
+            ..board(row_count, col_count, Topology::Square, false)
+        }
+    }
+
+    #[test]
+    fn place_mines_keeps_to_the_mines_per_tile() {
+        let game_config = stacked(5, 5, 40, 3);
+        for _ in 0..20 {
+            let mut tiles = new_grid(&game_config);
+            place_mines(
+                &game_config,
+                &Position::default(),
+                FirstClick::Anywhere,
+                &mut tiles,
+            );
+            let mines: Vec<i32> = tiles.iter().flatten().map(|tile| tile.mines).collect();
+            assert!(mines.iter().all(|mines| (0..=3).contains(mines)));
+            assert_eq!(mines.iter().sum::<i32>(), 40);
+        }
+        // Only a full stack on every other tile fits around a safe first click
+        let game_config = stacked(3, 3, 16, 2);
+        let first_move = Position {
+            layer: 0,
+            row: 1,
+            col: 1,
+        };
+        let mut tiles = new_grid(&game_config);
+        place_mines(&game_config, &first_move, FirstClick::Safe, &mut tiles);
+        for (i, row) in tiles.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                let mines = if (i, j) == (1, 1) { 0 } else { 2 };
+                assert_eq!(tile.mines, mines);
             }
         }
-    } else {
-        indicies.push(position_to_index(
-            game_config,
-            &Position {
-                row: position.row + 1,
-                col: position.col,
-            },
-        ));
-
-        if position.col > 0 {
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row,
-                    col: position.col - 1,
-                },
-            ));
+        assert_eq!(tiles[1][1].value, 16);
+    }
 
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row + 1,
-                    col: position.col - 1,
-                },
-            ));
+    #[test]
+    fn numbers_count_stacked_mines() {
+        let game_config = stacked(3, 3, 5, 3);
+        let mut tiles = new_grid(&game_config);
+        for (row, col, mines) in [(0, 0, 3), (2, 2, 2)] {
+            tiles[row][col].value = MINE_VALUE;
+            tiles[row][col].mines = mines;
         }
+        fill_numbers(&game_config, &mut tiles);
+        assert_eq!(tiles[1][1].value, 5);
+        assert_eq!(tiles[0][1].value, 3);
+        assert_eq!(tiles[1][2].value, 2);
+        assert_eq!(tiles[2][0].value, 0);
+    }
 
-        if position.col < game_config.col_count as i32 - 1 {
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row,
-                    col: position.col + 1,
-                },
-            ));
+    #[test]
+    fn fits_mines_leaves_a_safe_tile_in_play() {
+        let game_config = stacked(3, 3, 8, 1);
+        assert!(game_config.fits_mines());
+        assert!(
+            !GameConfig {
+                mine_count: 9,
+                ..game_config.clone()
+            }
+            .fits_mines()
+        );
+        assert!(
+            GameConfig {
+                mine_count: 16,
+                max_tile_mines: 2,
+                ..game_config.clone()
+            }
+            .fits_mines()
+        );
+        let mask = BoardMask::preset(Shape::Diamond, 3, 3);
+        assert_eq!(mask.as_ref().map(BoardMask::tile_count), Some(5));
+        assert!(
+            !GameConfig {
+                mask,
+                ..game_config
+            }
+            .fits_mines()
+        );
+    }
 
-            indicies.push(position_to_index(
-                game_config,
-                &Position {
-                    row: position.row + 1,
-                    col: position.col + 1,
-                },
-            ));
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_json_refuses_shapes_of_another_size() {
+        let game_config = GameConfig {
+            mine_count: 2,
+            mask: BoardMask::preset(Shape::Diamond, 3, 3),
+            ..board(3, 3, Topology::Square, false)
+        };
+        let saved = SavedGame::new(&game_config, GameState::Normal, &new_grid(&game_config));
+        let text = saved.to_json().unwrap();
+        assert!(SavedGame::from_json(&text).is_ok());
+        let wrong = SavedGame {
+            game_config: GameConfig {
+                mask: BoardMask::preset(Shape::Diamond, 4, 3),
+                ..game_config
+            },
+            ..saved
+        };
+        assert_eq!(
+            SavedGame::from_json(&wrong.to_json().unwrap()).unwrap_err(),
+            SavedGameError::Invalid("shape does not match the board".into())
+        );
+    }
+
+    fn liar(mines: &[(usize, usize)]) -> (GameConfig, Vec<Vec<Tile>>) {
+        let game_config = GameConfig {
+            mine_count: mines.len(),
+            liar: true,
+            ..board(3, 3, Topology::Square, false)
+        };
+        let mut tiles = new_grid(&game_config);
+        for (row, col) in mines {
+            tiles[*row][*col].value = MINE_VALUE;
+            tiles[*row][*col].mines = 1;
         }
+        (game_config, tiles)
     }
-    indicies
-}
 
-pub fn zero_pad(number: i32, length: i32) -> String {
-    let mut value = number.to_string();
-    let diff = length - value.len() as i32;
-    if diff > 0 {
-        value = "0".repeat(diff as usize) + value.as_str();
+    #[test]
+    fn lying_numbers_are_one_off_the_mines_around() {
+        let (game_config, mut tiles) = liar(&[(0, 0), (0, 1)]);
+        let mut truth = tiles.clone();
+        fill_numbers(
+            &GameConfig {
+                liar: false,
+                ..game_config.clone()
+            },
+            &mut truth,
+        );
+        for _ in 0..20 {
+            fill_numbers(&game_config, &mut tiles);
+            for (tile, real) in tiles.iter().flatten().zip(truth.iter().flatten()) {
+                if real.value == MINE_VALUE {
+                    assert_eq!(tile.value, MINE_VALUE);
+                } else {
+                    assert_eq!((tile.value - real.value).abs(), 1);
+                }
+            }
+            // Nothing around can only be a lie upwards
+            assert_eq!(tiles[2][2].value, 1);
+        }
+    }
+
+    #[test]
+    fn lying_numbers_chord_with_one_flag_more_or_less() {
+        let center = Position {
+            layer: 0,
+            row: 1,
+            col: 1,
+        };
+        for shown in [0, 2] {
+            let (game_config, mut tiles) = liar(&[(0, 0)]);
+            tiles[1][1].visible = true;
+            tiles[1][1].value = shown;
+            tiles[0][0].flags = 1;
+            assert_eq!(expand_selection(&game_config, &center, &mut tiles), None);
+            assert_eq!(
+                tiles.iter().flatten().filter(|tile| tile.visible).count(),
+                8
+            );
+            assert!(!tiles[0][0].visible);
+        }
+        // Without flags, or with as many flags as it shows, a lying number does nothing
+        for (shown, flagged) in [(1, &[][..]), (2, &[(0, 0), (2, 2)][..])] {
+            let (game_config, mut tiles) = liar(&[(0, 0), (0, 1)]);
+            tiles[1][1].visible = true;
+            tiles[1][1].value = shown;
+            for (row, col) in flagged {
+                tiles[*row][*col].flags = 1;
+            }
+            assert_eq!(expand_selection(&game_config, &center, &mut tiles), None);
+            assert_eq!(
+                tiles.iter().flatten().filter(|tile| tile.visible).count(),
+                1
+            );
+        }
     }
-    value
 }
@@ -0,0 +1,146 @@
+// Options of a game picked from lists, MetaEnum gives them names and indices for the menus
+// MetaEnum derive compares keys with to_ascii_lowercase
+#![allow(clippy::manual_ignore_case_cmp)]
+
+use meta_enum::{MetaEnum, ParseMetaEnumError};
+use slint::{ModelRc, SharedString, VecModel};
+
+use super::GameConfig;
+
+// Games played against the clock are lost when the time limit runs out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameMode {
+    #[default]
+    Classic,
+    // The board has to be cleared before the time limit
+    Countdown,
+    // As many boards as possible one after another before the time limit
+    TimeAttack,
+    // Square board without edges, scored by the safe tiles revealed before hitting a mine
+    Endless,
+}
+
+impl GameMode {
+    pub fn create_model() -> ModelRc<SharedString> {
+        let model: Vec<_> = GameMode::keys()
+            .into_iter()
+            .map(|key| key.into())
+            .collect();
+        VecModel::from_slice(&model)
+    }
+}
+
+// Tiles counted around a square tile, hexagonal tiles always have their 6 sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighbourhood {
+    // The 8 tiles touching it
+    #[default]
+    Classic,
+    // The 4 tiles sharing a side
+    Cross,
+    // The 8 tiles a chess knight reaches
+    Knight,
+    // The 24 tiles at most two steps away
+    Radius2,
+}
+
+impl Neighbourhood {
+    pub fn create_model() -> ModelRc<SharedString> {
+        let model: Vec<_> = Neighbourhood::keys()
+            .into_iter()
+            .map(|key| key.into())
+            .collect();
+        VecModel::from_slice(&model)
+    }
+}
+
+// Outline of the board, every shape but the rectangle leaves gaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    #[default]
+    Rectangle,
+    Heart,
+    Donut,
+    Diamond,
+}
+
+impl Shape {
+    pub fn create_model() -> ModelRc<SharedString> {
+        let model: Vec<_> = Shape::keys()
+            .into_iter()
+            .map(|key| key.into())
+            .collect();
+        VecModel::from_slice(&model)
+    }
+}
+
+// Shape of the tiles, hexagonal boards shift odd rows half a tile to the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    #[default]
+    Square,
+    Hexagonal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl GameDifficulty {
+    pub fn create_model() -> ModelRc<SharedString> {
+        let model: Vec<_> = GameDifficulty::keys()
+            .into_iter()
+            .map(|key| key.into())
+            .collect();
+        VecModel::from_slice(&model)
+    }
+
+    // Only the size and mines count of a layer, any topology and mines per tile have the same difficulties
+    // and shaped boards have the mines of the difficulty thinned out
+    pub fn from_config(game_config: &GameConfig) -> Option<Self> {
+        GameDifficulty::values()
+            .into_iter()
+            .map(GameDifficulty::from)
+            .find(|difficulty| {
+                let config = GameConfig::new(*difficulty);
+                let mine_count = match &game_config.mask {
+                    Some(mask) => mask.mine_count(config.mine_count),
+                    None => config.mine_count,
+                };
+                config.row_count == game_config.row_count
+                    && config.col_count == game_config.col_count
+                    && mine_count * game_config.layer_count == game_config.mine_count
+            })
+    }
+}
+
+// What the first click of a game is sure to hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, MetaEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FirstClick {
+    // No mine on the tile nor around it
+    #[default]
+    Opening,
+    // No mine on the tile
+    Safe,
+    // No guarantee
+    Anywhere,
+}
+
+impl FirstClick {
+    pub fn create_model() -> ModelRc<SharedString> {
+        let model: Vec<_> = FirstClick::keys()
+            .into_iter()
+            .map(|key| key.into())
+            .collect();
+        VecModel::from_slice(&model)
+    }
+}
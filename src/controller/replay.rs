@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use super::{
+    FORMAT_VERSION, GameConfig, GameOver, MINE_VALUE, Position, Tile, change_flag,
+    change_visibility, expand_selection, mine_hit, tile_at,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplayAction {
+    Reveal,
+    Flag,
+    Unflag,
+    Chord,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayEvent {
+    pub time_ms: u32,
+    pub action: ReplayAction,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub format_version: u32,
+    pub player: String,
+    pub game_config: GameConfig,
+    // Board before the first event, mines placed and possibly some flags
+    pub board: Vec<Vec<Tile>>,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new(game_config: &GameConfig, board: &[Vec<Tile>]) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            player: String::new(),
+            game_config: game_config.clone(),
+            board: board.to_vec(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, time: Duration, action: ReplayAction, position: Position) {
+        self.events.push(ReplayEvent {
+            time_ms: time.as_millis() as u32,
+            action,
+            position,
+        });
+    }
+
+    pub fn duration(&self) -> Duration {
+        let time_ms = self.events.last().map_or(0, |event| event.time_ms);
+        Duration::from_millis(time_ms as u64)
+    }
+}
+
+#[must_use]
+pub fn apply_event(
+    game_config: &GameConfig,
+    event: &ReplayEvent,
+    tiles: &mut [Vec<Tile>],
+) -> Option<GameOver> {
+    let position = &event.position;
+    match event.action {
+        ReplayAction::Reveal => {
+            change_visibility(game_config, tiles, position, true);
+            if tile_at(game_config, tiles, position).value == MINE_VALUE {
+                return mine_hit(game_config, tiles, position);
+            }
+            expand_selection(game_config, position, tiles)
+        }
+        ReplayAction::Chord => expand_selection(game_config, position, tiles),
+        // One more flag, tiles holding several mines take several
+        ReplayAction::Flag => {
+            let flags = tile_at(game_config, tiles, position).flags + 1;
+            change_flag(game_config, tiles, position, flags);
+            None
+        }
+        ReplayAction::Unflag => {
+            change_flag(game_config, tiles, position, 0);
+            None
+        }
+    }
+}
@@ -0,0 +1,614 @@
+// Minesweeper Clone `.rmv` replays (format version 1), all integers are big endian.
+//
+// Header:
+//   "*rmv", u16 format version, 4 reserved bytes,
+//   u16 result string size, u16 version info size, 4 reserved bytes,
+//   u16 player info size, u16 board size, u16 preflags size,
+//   u16 properties size, u16 extension size, u32 video size, u16 checksum size
+// Followed by the sections in this order:
+//   result string, checksum, version info, player info, board, preflags,
+//   properties, extension, video
+//
+// The video is a list of mouse events in pixels of the Clone window, which are
+// mapped onto tile actions by playing them against the board.
+
+use std::{error::Error, fmt};
+
+use super::{
+    GameConfig, GameDifficulty, MINE_VALUE, Neighbourhood, Position, Tile, Topology, fill_numbers,
+    new_grid,
+    replay::{Replay, ReplayAction, ReplayEvent, apply_event},
+};
+
+const MAGIC: &[u8; 4] = b"*rmv";
+const FORMAT_VERSION: u16 = 1;
+
+// Position of the top left square inside the Clone window and size of a square
+const BOARD_LEFT: i32 = 12;
+const BOARD_TOP: i32 = 56;
+const SQUARE_SIZE: i32 = 16;
+
+const EVENT_PADDING: u8 = 0;
+const EVENT_MOUSE_MOVE: u8 = 1;
+const EVENT_LEFT_DOWN: u8 = 2;
+const EVENT_LEFT_UP: u8 = 3;
+const EVENT_RIGHT_DOWN: u8 = 4;
+const EVENT_RIGHT_UP: u8 = 5;
+const EVENT_MIDDLE_DOWN: u8 = 6;
+const EVENT_MIDDLE_UP: u8 = 7;
+const EVENT_GAME_END: u8 = 16;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RmvError {
+    TooShort,
+    NotRmv,
+    UnsupportedVersion(u16),
+    InvalidBoard,
+    InvalidEvent(u8),
+    // Rows and columns are stored in a byte each
+    BoardTooLarge,
+    // Clone boards are plain rectangles of square tiles, named by what they lack
+    UnsupportedBoard(&'static str),
+}
+
+impl fmt::Display for RmvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RmvError::TooShort => write!(f, "file ends unexpectedly"),
+            RmvError::NotRmv => write!(f, "file is not an rmv replay"),
+            RmvError::UnsupportedVersion(version) => {
+                write!(f, "rmv format version {} is not supported", version)
+            }
+            RmvError::InvalidBoard => write!(f, "board of the replay is invalid"),
+            RmvError::InvalidEvent(event) => write!(f, "unknown replay event {}", event),
+            RmvError::BoardTooLarge => write!(f, "board is too large for an rmv replay"),
+            RmvError::UnsupportedBoard(feature) => {
+                write!(f, "rmv replays can not hold boards with {}", feature)
+            }
+        }
+    }
+}
+
+impl Error for RmvError {}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], RmvError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + length)
+            .ok_or(RmvError::TooShort)?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RmvError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RmvError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u24(&mut self) -> Result<u32, RmvError> {
+        let bytes = self.bytes(3)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, RmvError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+pub fn read_rmv(data: &[u8]) -> Result<Replay, RmvError> {
+    let mut reader = Reader { data, offset: 0 };
+    if reader.bytes(4).map_err(|_| RmvError::NotRmv)? != MAGIC {
+        return Err(RmvError::NotRmv);
+    }
+    let version = reader.u16()?;
+    if version != FORMAT_VERSION {
+        return Err(RmvError::UnsupportedVersion(version));
+    }
+    reader.bytes(4)?;
+    let result_string_size = reader.u16()? as usize;
+    let version_info_size = reader.u16()? as usize;
+    reader.bytes(4)?;
+    let player_info_size = reader.u16()? as usize;
+    let board_size = reader.u16()? as usize;
+    let preflags_size = reader.u16()? as usize;
+    let properties_size = reader.u16()? as usize;
+    let extension_size = reader.u16()? as usize;
+    let video_size = reader.u32()? as usize;
+    let checksum_size = reader.u16()? as usize;
+
+    reader.bytes(result_string_size + checksum_size + version_info_size)?;
+
+    // Player
+    let mut player = String::new();
+    let mut section = Reader {
+        data: reader.bytes(player_info_size)?,
+        offset: 0,
+    };
+    if player_info_size > 0 && section.u16()? > 0 {
+        let length = section.u8()? as usize;
+        player = String::from_utf8_lossy(section.bytes(length)?).into_owned();
+    }
+
+    // Board
+    let mut section = Reader {
+        data: reader.bytes(board_size)?,
+        offset: 0,
+    };
+    section.bytes(4)?;
+    let col_count = section.u8()? as usize;
+    let row_count = section.u8()? as usize;
+    let mine_count = section.u16()? as usize;
+    if row_count < 2 || col_count < 2 || mine_count >= row_count * col_count {
+        return Err(RmvError::InvalidBoard);
+    }
+    let game_config = GameConfig {
+        row_count,
+        col_count,
+        mine_count,
+        ..Default::default()
+    };
+    let mut board = new_grid(&game_config);
+    for _ in 0..mine_count {
+        let col = section.u8()? as usize;
+        let row = section.u8()? as usize;
+        let tile = board
+            .get_mut(row)
+            .and_then(|row| row.get_mut(col))
+            .ok_or(RmvError::InvalidBoard)?;
+        tile.value = MINE_VALUE;
+        tile.mines = 1;
+    }
+    fill_numbers(&game_config, &mut board);
+
+    // Preflags
+    let mut section = Reader {
+        data: reader.bytes(preflags_size)?,
+        offset: 0,
+    };
+    if preflags_size > 0 {
+        for _ in 0..section.u16()? {
+            let col = section.u8()? as usize;
+            let row = section.u8()? as usize;
+            let tile = board
+                .get_mut(row)
+                .and_then(|row| row.get_mut(col))
+                .ok_or(RmvError::InvalidBoard)?;
+            tile.flags = 1;
+        }
+    }
+
+    reader.bytes(properties_size + extension_size)?;
+
+    let mut replay = Replay::new(&game_config, &board);
+    replay.player = player;
+    let video_end = (reader.offset + video_size).min(data.len());
+    let mut video = Reader {
+        data: &data[reader.offset..video_end],
+        offset: 0,
+    };
+    replay.events = read_events(&mut video, &game_config, board)?;
+    Ok(replay)
+}
+
+fn read_events(
+    video: &mut Reader,
+    game_config: &GameConfig,
+    mut tiles: Vec<Vec<Tile>>,
+) -> Result<Vec<ReplayEvent>, RmvError> {
+    let mut events = Vec::new();
+    let mut left_down = false;
+    let mut right_down = false;
+    // Releasing the first button of a chord already chorded
+    let mut chorded = false;
+
+    while video.offset < video.data.len() {
+        let event = video.u8()?;
+        match event {
+            EVENT_PADDING => {
+                video.bytes(4)?;
+                continue;
+            }
+            EVENT_MOUSE_MOVE..=EVENT_MIDDLE_UP => {}
+            9..=14 | 18..=27 => {
+                // Board events are redundant with the mouse events
+                video.bytes(2)?;
+                continue;
+            }
+            15..=17 => break,
+            _ => return Err(RmvError::InvalidEvent(event)),
+        }
+        let time_ms = video.u24()?;
+        let x = video.u16()? as i32 - BOARD_LEFT;
+        let y = video.u16()? as i32 - BOARD_TOP;
+        let position = (x >= 0 && y >= 0)
+            .then_some(Position {
+                row: y / SQUARE_SIZE,
+                col: x / SQUARE_SIZE,
+                layer: 0,
+            })
+            .filter(|position| {
+                (position.row as usize) < game_config.row_count
+                    && (position.col as usize) < game_config.col_count
+            });
+
+        let released = match event {
+            EVENT_LEFT_DOWN => {
+                left_down = true;
+                false
+            }
+            EVENT_RIGHT_DOWN => {
+                right_down = true;
+                false
+            }
+            EVENT_LEFT_UP => {
+                left_down = false;
+                true
+            }
+            EVENT_RIGHT_UP => {
+                right_down = false;
+                true
+            }
+            _ => false,
+        };
+        let Some(position) = position else {
+            continue;
+        };
+        let tile = &tiles[position.row as usize][position.col as usize];
+        let action = match event {
+            EVENT_RIGHT_DOWN if !left_down && !tile.visible => Some(if tile.flags > 0 {
+                ReplayAction::Unflag
+            } else {
+                ReplayAction::Flag
+            }),
+            _ if released && chorded => {
+                chorded = false;
+                None
+            }
+            _ if released && (left_down || right_down) => {
+                chorded = true;
+                tile.visible.then_some(ReplayAction::Chord)
+            }
+            EVENT_LEFT_UP if !tile.visible && tile.flags == 0 => Some(ReplayAction::Reveal),
+            EVENT_MIDDLE_UP if tile.visible => Some(ReplayAction::Chord),
+            _ => None,
+        };
+        if let Some(action) = action {
+            let event = ReplayEvent {
+                time_ms,
+                action,
+                position,
+            };
+            let lost = apply_event(game_config, &event, &mut tiles);
+            events.push(event);
+            if lost.is_some() {
+                break;
+            }
+        }
+    }
+    Ok(events)
+}
+
+pub fn write_rmv(replay: &Replay) -> Result<Vec<u8>, RmvError> {
+    let game_config = &replay.game_config;
+    if game_config.topology != Topology::Square {
+        return Err(RmvError::UnsupportedBoard("hexagonal tiles"));
+    }
+    if game_config.wrap {
+        return Err(RmvError::UnsupportedBoard("wrapped edges"));
+    }
+    if game_config.layer_count > 1 {
+        return Err(RmvError::UnsupportedBoard("several layers"));
+    }
+    if game_config.neighbourhood != Neighbourhood::Classic {
+        return Err(RmvError::UnsupportedBoard("other neighbourhoods"));
+    }
+    if game_config.max_tile_mines > 1 {
+        return Err(RmvError::UnsupportedBoard("several mines per tile"));
+    }
+    if game_config.mask.is_some() {
+        return Err(RmvError::UnsupportedBoard("gaps"));
+    }
+    if game_config.liar {
+        return Err(RmvError::UnsupportedBoard("lying numbers"));
+    }
+    if game_config.lives > 1 {
+        return Err(RmvError::UnsupportedBoard("several lives"));
+    }
+    let (Ok(col_count), Ok(row_count)) = (
+        u8::try_from(game_config.col_count),
+        u8::try_from(game_config.row_count),
+    ) else {
+        return Err(RmvError::BoardTooLarge);
+    };
+
+    let version_info = format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+    let mut player_info = Vec::new();
+    if !replay.player.is_empty() {
+        let player = &replay.player.as_bytes()[..replay.player.len().min(u8::MAX as usize)];
+        player_info.extend(1u16.to_be_bytes());
+        player_info.push(player.len() as u8);
+        player_info.extend(player);
+    }
+
+    let mut board = vec![0; 4];
+    board.push(col_count);
+    board.push(row_count);
+    let mine_count = u16::try_from(game_config.mine_count).map_err(|_| RmvError::BoardTooLarge)?;
+    board.extend(mine_count.to_be_bytes());
+    let mut preflags = Vec::new();
+    for (i, row) in replay.board.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if tile.value == MINE_VALUE {
+                board.extend([j as u8, i as u8]);
+            }
+            if tile.flags > 0 {
+                preflags.extend([j as u8, i as u8]);
+            }
+        }
+    }
+    let flags_used = !preflags.is_empty()
+        || replay
+            .events
+            .iter()
+            .any(|event| event.action == ReplayAction::Flag);
+    let preflags = [((preflags.len() / 2) as u16).to_be_bytes().to_vec(), preflags].concat();
+    // Every mine takes two bytes of the board section
+    let (Ok(board_size), Ok(preflags_size)) =
+        (u16::try_from(board.len()), u16::try_from(preflags.len()))
+    else {
+        return Err(RmvError::BoardTooLarge);
+    };
+
+    let level = match GameDifficulty::from_config(game_config) {
+        Some(GameDifficulty::Easy) => 0,
+        Some(GameDifficulty::Medium) => 1,
+        Some(GameDifficulty::Hard) => 2,
+        None => 3,
+    };
+    // Question marks, no flags, mode, level
+    let properties = [0, !flags_used as u8, 0, level];
+
+    let mut video = Vec::new();
+    for event in &replay.events {
+        let (down, up) = match event.action {
+            ReplayAction::Reveal => (EVENT_LEFT_DOWN, EVENT_LEFT_UP),
+            ReplayAction::Flag | ReplayAction::Unflag => (EVENT_RIGHT_DOWN, EVENT_RIGHT_UP),
+            ReplayAction::Chord => (EVENT_MIDDLE_DOWN, EVENT_MIDDLE_UP),
+        };
+        let x = (BOARD_LEFT + event.position.col * SQUARE_SIZE + SQUARE_SIZE / 2) as u16;
+        let y = (BOARD_TOP + event.position.row * SQUARE_SIZE + SQUARE_SIZE / 2) as u16;
+        for kind in [EVENT_MOUSE_MOVE, down, up] {
+            video.push(kind);
+            video.extend(&event.time_ms.min(0xFF_FFFF).to_be_bytes()[1..]);
+            video.extend(x.to_be_bytes());
+            video.extend(y.to_be_bytes());
+        }
+    }
+    video.push(EVENT_GAME_END);
+
+    let mut data = MAGIC.to_vec();
+    data.extend(FORMAT_VERSION.to_be_bytes());
+    data.extend([0; 4]);
+    data.extend(0u16.to_be_bytes()); // Result string
+    data.extend((version_info.len() as u16).to_be_bytes());
+    data.extend([0; 4]);
+    data.extend((player_info.len() as u16).to_be_bytes());
+    data.extend(board_size.to_be_bytes());
+    data.extend(preflags_size.to_be_bytes());
+    data.extend((properties.len() as u16).to_be_bytes());
+    data.extend(0u16.to_be_bytes()); // Extension
+    data.extend((video.len() as u32).to_be_bytes());
+    data.extend(0u16.to_be_bytes()); // Checksum
+    data.extend(version_info.as_bytes());
+    data.extend(player_info);
+    data.extend(board);
+    data.extend(preflags);
+    data.extend(properties);
+    data.extend(video);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::{Shape, mask::BoardMask};
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/replay.rmv");
+
+    fn event(time_ms: u32, action: ReplayAction, row: i32, col: i32) -> ReplayEvent {
+        ReplayEvent {
+            time_ms,
+            action,
+            position: Position { layer: 0, row, col },
+        }
+    }
+
+    #[test]
+    fn read_parses_the_fixture() {
+        let replay = read_rmv(FIXTURE).unwrap();
+        assert_eq!(replay.player, "tester");
+        assert_eq!(
+            replay.game_config,
+            GameConfig {
+                row_count: 3,
+                col_count: 4,
+                mine_count: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(replay.board[0][0].value, MINE_VALUE);
+        assert_eq!(replay.board[2][3].value, MINE_VALUE);
+        assert_eq!(replay.board[2][3].flags, 1);
+        assert_eq!(replay.board[1][1].value, 1);
+        assert_eq!(
+            replay.events,
+            [
+                event(200, ReplayAction::Reveal, 1, 1),
+                event(550, ReplayAction::Reveal, 2, 0),
+                event(900, ReplayAction::Flag, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_then_read_gives_back_the_replay() {
+        let game_config = GameConfig {
+            row_count: 5,
+            col_count: 6,
+            mine_count: 3,
+            ..Default::default()
+        };
+        let mut board = new_grid(&game_config);
+        for (row, col) in [(0, 5), (2, 2), (4, 0)] {
+            board[row][col].value = MINE_VALUE;
+            board[row][col].mines = 1;
+        }
+        fill_numbers(&game_config, &mut board);
+        board[4][0].flags = 1;
+        let mut replay = Replay::new(&game_config, &board);
+        replay.player = "player".into();
+        replay.events = vec![
+            event(120, ReplayAction::Reveal, 0, 0),
+            event(480, ReplayAction::Flag, 2, 2),
+            event(700, ReplayAction::Chord, 1, 1),
+            event(900, ReplayAction::Unflag, 2, 2),
+        ];
+
+        let read = read_rmv(&write_rmv(&replay).unwrap()).unwrap();
+        assert_eq!(read.player, replay.player);
+        assert_eq!(read.game_config, replay.game_config);
+        assert_eq!(read.board, replay.board);
+        assert_eq!(read.events, replay.events);
+    }
+
+    #[test]
+    fn write_refuses_boards_too_large() {
+        let game_config = GameConfig {
+            row_count: 300,
+            col_count: 2,
+            mine_count: 1,
+            ..Default::default()
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(write_rmv(&replay).unwrap_err(), RmvError::BoardTooLarge);
+    }
+
+    #[test]
+    fn write_refuses_hexagonal_boards() {
+        let game_config = GameConfig {
+            topology: Topology::Hexagonal,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("hexagonal tiles")
+        );
+    }
+
+    #[test]
+    fn write_refuses_wrapped_boards() {
+        let game_config = GameConfig {
+            wrap: true,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("wrapped edges")
+        );
+    }
+
+    #[test]
+    fn write_refuses_layered_boards() {
+        let easy = GameConfig::new(GameDifficulty::Easy);
+        let game_config = GameConfig {
+            mine_count: easy.mine_count * 2,
+            layer_count: 2,
+            ..easy
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("several layers")
+        );
+    }
+
+    #[test]
+    fn write_refuses_other_neighbourhoods() {
+        let game_config = GameConfig {
+            neighbourhood: Neighbourhood::Knight,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("other neighbourhoods")
+        );
+    }
+
+    #[test]
+    fn write_refuses_several_mines_per_tile() {
+        let game_config = GameConfig {
+            max_tile_mines: 2,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("several mines per tile")
+        );
+    }
+
+    #[test]
+    fn write_refuses_shaped_boards() {
+        let easy = GameConfig::new(GameDifficulty::Easy);
+        let game_config = GameConfig {
+            mask: BoardMask::preset(Shape::Diamond, easy.row_count, easy.col_count),
+            ..easy
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("gaps")
+        );
+    }
+
+    #[test]
+    fn write_refuses_lying_numbers() {
+        let game_config = GameConfig {
+            liar: true,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("lying numbers")
+        );
+    }
+
+    #[test]
+    fn write_refuses_several_lives() {
+        let game_config = GameConfig {
+            lives: 3,
+            ..GameConfig::new(GameDifficulty::Easy)
+        };
+        let replay = Replay::new(&game_config, &new_grid(&game_config));
+        assert_eq!(
+            write_rmv(&replay).unwrap_err(),
+            RmvError::UnsupportedBoard("several lives")
+        );
+    }
+}
@@ -0,0 +1,359 @@
+// Preferences of the player, kept as toml in the user config directory
+
+use std::collections::BTreeMap;
+
+use meta_enum::MetaEnum;
+
+use super::{
+    Appearance, ColorPalette, FORMAT_VERSION, FirstClick, GameConfig, GameDifficulty, GameMode,
+    KeyBindings, Neighbourhood, Shape, Topology, mask::BoardMask, statistics::config_name,
+};
+
+#[cfg(feature = "persistence")]
+pub const SETTINGS_FILE: &str = "settings.toml";
+pub const DEFAULT_SKIN: &str = "Modern";
+pub const DEFAULT_TILE_SIZE: f32 = 40.0;
+pub const DEFAULT_FONT_SIZE: f32 = 28.0;
+pub const SPACE_KEY: &str = "Space";
+// Up, down, left and right always move the cursor, in the order of the key bindings
+const VIM_KEYS: [&str; 4] = ["k", "j", "h", "l"];
+pub const HINT_KEY: &str = "?";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardZoom {
+    // Logical pixels
+    pub tile_size: f32,
+    pub fit_to_window: bool,
+}
+
+impl Default for BoardZoom {
+    fn default() -> Self {
+        Self {
+            tile_size: DEFAULT_TILE_SIZE,
+            fit_to_window: false,
+        }
+    }
+}
+
+// Physical pixels of the main window when it was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsError {
+    CustomTooSmall,
+    CustomTooManyMines,
+    InvalidShape,
+    ShapeTooSmall,
+    EmptyKey,
+    DuplicateKey(String),
+    // Taken by a shortcut of the board for another action
+    ReservedKey(String),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::CustomTooSmall => write!(f, "custom board must be at least 2x2"),
+            SettingsError::CustomTooManyMines => {
+                write!(f, "custom board needs at least one mine and one safe tile")
+            }
+            SettingsError::InvalidShape => write!(f, "shape rows must all have the same length"),
+            SettingsError::ShapeTooSmall => {
+                write!(f, "shape leaves too few tiles for the mines on some board")
+            }
+            SettingsError::EmptyKey => write!(f, "every key binding needs a key"),
+            SettingsError::DuplicateKey(key) => write!(f, "key '{key}' is bound twice"),
+            SettingsError::ReservedKey(key) => {
+                write!(f, "key '{key}' is already a shortcut of the board")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+// Missing fields of older files take their default
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Settings {
+    pub format_version: u32,
+    // Last difficulty played, the custom board when custom_difficulty is set
+    pub difficulty: GameDifficulty,
+    pub custom_difficulty: bool,
+    pub custom: GameConfig,
+    // Applies to every difficulty
+    pub topology: Topology,
+    pub wrap: bool,
+    pub layer_count: usize,
+    pub neighbourhood: Neighbourhood,
+    pub max_tile_mines: usize,
+    pub shape: Shape,
+    // Mask opened from a file, used instead of the shape
+    pub mask: Option<BoardMask>,
+    pub liar: bool,
+    pub lives: usize,
+    pub mode: GameMode,
+    pub time_limit_secs: u64,
+    pub first_click: FirstClick,
+    pub font_size: f32,
+    pub palette: ColorPalette,
+    pub skin: String,
+    pub appearance: Appearance,
+    pub key_bindings: KeyBindings,
+    pub remember_window: bool,
+    pub window: Option<WindowGeometry>,
+    // Keyed by config_name
+    pub zoom: BTreeMap<String, BoardZoom>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            difficulty: GameDifficulty::Medium,
+            custom_difficulty: false,
+            custom: GameConfig {
+                row_count: 20,
+                col_count: 20,
+                mine_count: 60,
+                ..Default::default()
+            },
+            topology: Topology::default(),
+            wrap: false,
+            layer_count: 1,
+            neighbourhood: Neighbourhood::default(),
+            max_tile_mines: 1,
+            shape: Shape::default(),
+            mask: None,
+            liar: false,
+            lives: 1,
+            mode: GameMode::default(),
+            time_limit_secs: 180,
+            first_click: FirstClick::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            palette: ColorPalette::default(),
+            skin: DEFAULT_SKIN.into(),
+            appearance: Appearance::default(),
+            key_bindings: default_key_bindings(),
+            remember_window: true,
+            window: None,
+            zoom: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl super::storage::Versioned for Settings {
+    fn format_version_mut(&mut self) -> &mut u32 {
+        &mut self.format_version
+    }
+
+    // Settings edited by hand could bind keys the dialog refuses
+    fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+impl Settings {
+    pub fn game_config(&self) -> GameConfig {
+        let game_config = if self.custom_difficulty {
+            self.custom.clone()
+        } else {
+            GameConfig::new(self.difficulty)
+        };
+        // Endless boards have no edges to shape and only play the density of the board
+        if self.mode == GameMode::Endless {
+            return GameConfig {
+                mode: self.mode,
+                ..game_config
+            };
+        }
+        self.board_config(game_config)
+    }
+
+    // Tile and shape options applied to the size and mines of a board
+    fn board_config(&self, game_config: GameConfig) -> GameConfig {
+        // Masks fit the board, the mines are thinned out to the tiles left in play
+        let mask = match &self.mask {
+            Some(mask) => Some(mask.resized(game_config.row_count, game_config.col_count)),
+            None => BoardMask::preset(self.shape, game_config.row_count, game_config.col_count),
+        };
+        let mine_count = mask
+            .as_ref()
+            .map_or(game_config.mine_count, |mask| mask.mine_count(game_config.mine_count));
+        // Every layer gets the mines of a flat board
+        let layer_count = self.layer_count.max(1);
+        GameConfig {
+            mine_count: mine_count * layer_count,
+            topology: self.topology,
+            wrap: self.wrap,
+            layer_count,
+            // Hexagonal tiles always touch their six neighbours
+            neighbourhood: match self.topology {
+                Topology::Square => self.neighbourhood,
+                Topology::Hexagonal => Neighbourhood::Classic,
+            },
+            max_tile_mines: self.max_tile_mines.max(1),
+            mask,
+            liar: self.liar,
+            lives: self.lives.max(1),
+            mode: self.mode,
+            time_limit_secs: self.time_limit_secs.max(1),
+            ..game_config
+        }
+    }
+
+    pub fn zoom(&self, game_config: &GameConfig) -> BoardZoom {
+        self.zoom
+            .get(&config_name(game_config))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_zoom(&mut self, game_config: &GameConfig, zoom: BoardZoom) {
+        self.zoom.insert(config_name(game_config), zoom);
+    }
+
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let custom = &self.custom;
+        if custom.row_count < 2 || custom.col_count < 2 {
+            return Err(SettingsError::CustomTooSmall);
+        }
+        if custom.mine_count == 0 || custom.mine_count >= custom.row_count * custom.col_count {
+            return Err(SettingsError::CustomTooManyMines);
+        }
+        if self.mask.as_ref().is_some_and(|mask| mask.size().is_none()) {
+            return Err(SettingsError::InvalidShape);
+        }
+        // A shape shrunk to a small board can leave too few tiles in play
+        let boards = GameDifficulty::values()
+            .into_iter()
+            .map(|difficulty| GameConfig::new(difficulty.into()))
+            .chain([custom.clone()]);
+        for board in boards {
+            if !self.board_config(board).fits_mines() {
+                return Err(SettingsError::ShapeTooSmall);
+            }
+        }
+        let bindings = &self.key_bindings;
+        let keys = [
+            &bindings.up,
+            &bindings.down,
+            &bindings.left,
+            &bindings.right,
+            &bindings.reveal,
+            &bindings.flag,
+            &bindings.chord,
+            &bindings.pause,
+        ];
+        for (i, key) in keys.iter().enumerate() {
+            if key.is_empty() {
+                return Err(SettingsError::EmptyKey);
+            }
+            if keys[..i].contains(key) {
+                return Err(SettingsError::DuplicateKey(key.to_string()));
+            }
+            // The board checks the vim keys, the hint and the difficulty digits before the bindings
+            let vim_key = VIM_KEYS.iter().position(|vim_key| *vim_key == key.as_str());
+            let digit = key
+                .parse::<u32>()
+                .is_ok_and(|digit| (1..=9).contains(&digit));
+            if digit || key.as_str() == HINT_KEY || vim_key.is_some_and(|vim_key| vim_key != i) {
+                return Err(SettingsError::ReservedKey(key.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn load() -> Self {
+        super::storage::load_toml(SETTINGS_FILE)
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn save(&self) -> std::io::Result<()> {
+        super::storage::save_toml(SETTINGS_FILE, self)
+    }
+}
+
+// WASD to move but d chords, so moving right is on l like the vim keys
+pub fn default_key_bindings() -> KeyBindings {
+    KeyBindings {
+        up: "w".into(),
+        down: "s".into(),
+        left: "a".into(),
+        right: "l".into(),
+        reveal: SPACE_KEY.into(),
+        flag: "f".into(),
+        chord: "d".into(),
+        pause: "p".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert_eq!(Settings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_refuses_shortcuts_of_the_board() {
+        let mut settings = Settings::default();
+        settings.key_bindings.flag = "k".into();
+        assert_eq!(
+            settings.validate(),
+            Err(SettingsError::ReservedKey("k".into()))
+        );
+        settings.key_bindings.flag = "2".into();
+        assert_eq!(
+            settings.validate(),
+            Err(SettingsError::ReservedKey("2".into()))
+        );
+        settings.key_bindings.flag = HINT_KEY.into();
+        assert_eq!(
+            settings.validate(),
+            Err(SettingsError::ReservedKey(HINT_KEY.into()))
+        );
+        // A vim key bound to its own direction changes nothing
+        settings.key_bindings.flag = "f".into();
+        settings.key_bindings.up = "k".into();
+        assert_eq!(settings.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_refuses_shapes_too_small_for_a_board() {
+        // Two tiles side by side, the smaller boards only keep one of them
+        let mut tiles = vec![vec![false; 16]; 16];
+        tiles[0][0] = true;
+        tiles[0][1] = true;
+        let mut settings = Settings {
+            mask: Some(BoardMask {
+                name: "pair".into(),
+                preset: None,
+                tiles,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(settings.validate(), Err(SettingsError::ShapeTooSmall));
+        if let Some(mask) = &mut settings.mask {
+            mask.tiles[1].pop();
+        }
+        assert_eq!(settings.validate(), Err(SettingsError::InvalidShape));
+        settings.mask = None;
+        settings.shape = Shape::Heart;
+        assert_eq!(settings.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,128 @@
+// Skins of the user, one directory per skin in the data directory with an image per tile state:
+//   hidden, blank, 1-8, mine and flag as .svg or .png
+// and an optional skin.json with the colours and font around the board:
+//   { "background": "#202020", "text": "#e0e0e0", "font_family": "Noto Sans" }
+// Missing files are taken from the fallback skin.
+
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use slint::{Color, Image, Model as _, ModelRc, VecModel};
+
+use super::Skin;
+
+pub const SKINS_DIR: &str = "skins";
+pub const SKIN_FILE: &str = "skin.json";
+const IMAGE_EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+#[derive(Debug)]
+pub enum SkinError {
+    Io(io::Error),
+    Image { file: PathBuf },
+    SkinFile(serde_json::Error),
+    Color { value: String },
+}
+
+impl fmt::Display for SkinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkinError::Io(err) => write!(f, "{err}"),
+            SkinError::Image { file } => write!(f, "cannot load image {}", file.display()),
+            SkinError::SkinFile(err) => write!(f, "invalid {SKIN_FILE}: {err}"),
+            SkinError::Color { value } => write!(f, "invalid colour '{value}', expected #rrggbb"),
+        }
+    }
+}
+
+impl Error for SkinError {}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct SkinFile {
+    background: Option<String>,
+    text: Option<String>,
+    font_family: Option<String>,
+}
+
+pub fn skins_dir() -> Option<PathBuf> {
+    super::storage::data_path(SKINS_DIR)
+}
+
+// Names of the skin directories, sorted
+pub fn user_skins() -> Vec<String> {
+    let Some(entries) = skins_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn load_user_skin(name: &str, fallback: &Skin) -> Result<Skin, SkinError> {
+    let dir = skins_dir()
+        .ok_or_else(|| SkinError::Io(io::Error::new(io::ErrorKind::NotFound, "no data directory")))?;
+    load_skin(name, &dir.join(name), fallback)
+}
+
+pub fn load_skin(name: &str, dir: &Path, fallback: &Skin) -> Result<Skin, SkinError> {
+    if !dir.is_dir() {
+        return Err(SkinError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a directory", dir.display()),
+        )));
+    }
+    let skin_file = match fs::read_to_string(dir.join(SKIN_FILE)) {
+        Ok(text) => serde_json::from_str(&text).map_err(SkinError::SkinFile)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => SkinFile::default(),
+        Err(err) => return Err(SkinError::Io(err)),
+    };
+
+    let mut numbers = Vec::new();
+    for number in 1..=8 {
+        let fallback = fallback.numbers.row_data(number - 1).unwrap_or_default();
+        numbers.push(load_image(dir, &number.to_string(), fallback)?);
+    }
+    Ok(Skin {
+        name: name.into(),
+        hidden: load_image(dir, "hidden", fallback.hidden.clone())?,
+        blank: load_image(dir, "blank", fallback.blank.clone())?,
+        numbers: ModelRc::new(VecModel::from(numbers)),
+        mine: load_image(dir, "mine", fallback.mine.clone())?,
+        flag: load_image(dir, "flag", fallback.flag.clone())?,
+        background: parse_color(skin_file.background, fallback.background)?,
+        text: parse_color(skin_file.text, fallback.text)?,
+        font_family: skin_file
+            .font_family
+            .map_or(fallback.font_family.clone(), Into::into),
+    })
+}
+
+fn load_image(dir: &Path, name: &str, fallback: Image) -> Result<Image, SkinError> {
+    for extension in IMAGE_EXTENSIONS {
+        let file = dir.join(name).with_extension(extension);
+        if file.is_file() {
+            return Image::load_from_path(&file).map_err(|_| SkinError::Image { file });
+        }
+    }
+    Ok(fallback)
+}
+
+fn parse_color(value: Option<String>, fallback: Color) -> Result<Color, SkinError> {
+    let Some(value) = value else {
+        return Ok(fallback);
+    };
+    value
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(Color::from_argb_encoded)
+        .map(|color| color.with_alpha(1.0))
+        .ok_or(SkinError::Color { value })
+}
@@ -0,0 +1,271 @@
+// Certain moves found from the revealed numbers, flags are trusted to be right
+// Numbers give a range of mines so lying numbers fit in too
+// A mine deduction is a tile holding as many mines as a tile can
+
+use std::collections::{BTreeMap, btree_map::Entry};
+
+use super::{
+    GameConfig, MINE_VALUE, Position, Tile, grid_position, index_to_position, marked_mines,
+    position_to_index, surronding_indicies, tile_at,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduction {
+    Safe,
+    Mine,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub position: Position,
+    pub deduction: Deduction,
+}
+
+// Hidden tiles, sorted by index, holding between min and max mines
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constraint {
+    tiles: Vec<usize>,
+    min: i32,
+    max: i32,
+}
+
+// Every hidden tile whose content follows from the numbers, repeated until nothing new is found
+pub fn deductions(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> Vec<Hint> {
+    let mut known = BTreeMap::new();
+    loop {
+        let constraints = constraints(game_config, tiles, &known);
+        let mut found = false;
+        for (index, deduction) in deduce(&constraints, game_config.max_tile_mines as i32) {
+            if let Entry::Vacant(entry) = known.entry(index) {
+                entry.insert(deduction);
+                found = true;
+            }
+        }
+        if !found {
+            break;
+        }
+    }
+    known
+        .into_iter()
+        .map(|(index, deduction)| Hint {
+            position: index_to_position(game_config, index),
+            deduction,
+        })
+        .collect()
+}
+
+// Safe tiles first, they move the game on
+pub fn hint(game_config: &GameConfig, tiles: &[Vec<Tile>]) -> Option<Hint> {
+    let deductions = deductions(game_config, tiles);
+    deductions
+        .iter()
+        .find(|hint| hint.deduction == Deduction::Safe)
+        .or(deductions.first())
+        .cloned()
+}
+
+fn constraints(
+    game_config: &GameConfig,
+    tiles: &[Vec<Tile>],
+    known: &BTreeMap<usize, Deduction>,
+) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    let mut all_hidden = Vec::new();
+    let mut all_mines = 0;
+    let full = game_config.max_tile_mines as i32;
+    for (i, row) in tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let position = grid_position(game_config, i, j);
+            let index = position_to_index(game_config, &position);
+            if tile.disabled {
+                continue;
+            }
+            if tile.flags > 0 || tile.exploded {
+                all_mines += marked_mines(tile);
+            } else if known.get(&index) == Some(&Deduction::Mine) {
+                all_mines += full;
+            } else if !tile.visible && !known.contains_key(&index) {
+                all_hidden.push(index);
+            }
+            if !tile.visible || tile.value == MINE_VALUE {
+                continue;
+            }
+
+            let mut hidden = Vec::new();
+            let mut mines = 0;
+            for around in surronding_indicies(game_config, &position) {
+                let tile = tile_at(game_config, tiles, &index_to_position(game_config, around));
+                if tile.flags > 0 || tile.exploded {
+                    mines += marked_mines(tile);
+                } else if known.get(&around) == Some(&Deduction::Mine) {
+                    mines += full;
+                } else if !tile.visible && !known.contains_key(&around) {
+                    hidden.push(around);
+                }
+            }
+            if hidden.is_empty() {
+                continue;
+            }
+            hidden.sort_unstable();
+            // A lying number is one off either way, unless the hidden tiles can not hold that count
+            let counts = if game_config.liar {
+                vec![tile.value - 1, tile.value + 1]
+            } else {
+                vec![tile.value]
+            };
+            let most = mines + hidden.len() as i32 * full;
+            let counts: Vec<i32> = counts
+                .into_iter()
+                .filter(|count| (mines..=most).contains(count))
+                .collect();
+            let (Some(min), Some(max)) = (counts.first(), counts.last()) else {
+                continue;
+            };
+            constraints.push(Constraint {
+                tiles: hidden,
+                min: min - mines,
+                max: max - mines,
+            });
+        }
+    }
+
+    // The mines not flagged yet are somewhere in the hidden tiles
+    if !all_hidden.is_empty() {
+        let mines = game_config.mine_count as i32 - all_mines;
+        constraints.push(Constraint {
+            tiles: all_hidden,
+            min: mines,
+            max: mines,
+        });
+    }
+    constraints
+}
+
+fn deduce(constraints: &[Constraint], max_tile_mines: i32) -> Vec<(usize, Deduction)> {
+    let mut found = Vec::new();
+    for constraint in constraints {
+        found.extend(certain(
+            &constraint.tiles,
+            constraint.min,
+            constraint.max,
+            max_tile_mines,
+        ));
+    }
+
+    // Tiles of a constraint which are not in one of its subsets hold the difference of their mines
+    for a in constraints {
+        for b in constraints {
+            if a.tiles.len() >= b.tiles.len()
+                || !a.tiles.iter().all(|index| b.tiles.binary_search(index).is_ok())
+            {
+                continue;
+            }
+            let rest: Vec<usize> = b
+                .tiles
+                .iter()
+                .filter(|index| a.tiles.binary_search(index).is_err())
+                .copied()
+                .collect();
+            found.extend(certain(
+                &rest,
+                b.min - a.max,
+                b.max - a.min,
+                max_tile_mines,
+            ));
+        }
+    }
+    found
+}
+
+// Tiles which must all be safe or all be full of mines for their count to fit
+fn certain(tiles: &[usize], min: i32, max: i32, max_tile_mines: i32) -> Vec<(usize, Deduction)> {
+    let deduction = if max <= 0 {
+        Deduction::Safe
+    } else if min >= tiles.len() as i32 * max_tile_mines {
+        Deduction::Mine
+    } else {
+        return Vec::new();
+    };
+    tiles.iter().map(|index| (*index, deduction)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::{fill_numbers, new_grid};
+
+    fn board(
+        row_count: usize,
+        col_count: usize,
+        mines: &[(usize, usize)],
+    ) -> (GameConfig, Vec<Vec<Tile>>) {
+        let game_config = GameConfig {
+            row_count,
+            col_count,
+            mine_count: mines.len(),
+            ..Default::default()
+        };
+        let mut tiles = new_grid(&game_config);
+        for (row, col) in mines {
+            tiles[*row][*col].value = MINE_VALUE;
+            tiles[*row][*col].mines = 1;
+        }
+        fill_numbers(&game_config, &mut tiles);
+        (game_config, tiles)
+    }
+
+    fn hint_at(row: i32, col: i32, deduction: Deduction) -> Hint {
+        Hint {
+            position: Position { layer: 0, row, col },
+            deduction,
+        }
+    }
+
+    #[test]
+    fn numbers_give_safe_tiles_and_then_mines() {
+        let (game_config, mut tiles) = board(3, 3, &[(0, 0)]);
+        for (i, row) in tiles.iter_mut().enumerate() {
+            for (j, tile) in row.iter_mut().enumerate() {
+                tile.visible = i > 0 || j > 1;
+            }
+        }
+        // The 0 in the corner clears its neighbour, which leaves the 1 below only one tile
+        assert_eq!(
+            deductions(&game_config, &tiles),
+            [
+                hint_at(0, 0, Deduction::Mine),
+                hint_at(0, 1, Deduction::Safe)
+            ]
+        );
+        assert_eq!(
+            hint(&game_config, &tiles),
+            Some(hint_at(0, 1, Deduction::Safe))
+        );
+    }
+
+    #[test]
+    fn subsets_clear_the_tiles_they_leave_out() {
+        let (game_config, mut tiles) = board(3, 5, &[(0, 1), (0, 4)]);
+        for tile in tiles[1..].iter_mut().flatten() {
+            tile.visible = true;
+        }
+        // Every 1 alone could be any of its tiles, but the 1 of the corner sits inside the next one
+        assert_eq!(
+            deductions(&game_config, &tiles),
+            [hint_at(0, 2, Deduction::Safe)]
+        );
+    }
+
+    #[test]
+    fn mine_count_clears_the_board_when_every_mine_is_flagged() {
+        let (game_config, mut tiles) = board(3, 3, &[(1, 1)]);
+        tiles[1][1].flags = 1;
+        let expected: Vec<_> = (0..3)
+            .flat_map(|row| (0..3).map(move |col| (row, col)))
+            .filter(|position| *position != (1, 1))
+            .map(|(row, col)| hint_at(row, col, Deduction::Safe))
+            .collect();
+        assert_eq!(deductions(&game_config, &tiles), expected);
+        assert_eq!(hint(&game_config, &board(3, 3, &[(1, 1)]).1), None);
+    }
+}
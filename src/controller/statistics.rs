@@ -0,0 +1,215 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use meta_enum::MetaEnum;
+use slint::{ModelRc, StandardListViewItem, VecModel};
+
+use super::{
+    FORMAT_VERSION, GameConfig, GameDifficulty, GameMode, Neighbourhood, Topology, format_time,
+    metrics::GameMetrics,
+};
+
+#[cfg(feature = "persistence")]
+pub const STATISTICS_FILE: &str = "statistics.json";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DifficultyStatistics {
+    pub played: u32,
+    pub won: u32,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub best_time_ms: Option<u64>,
+    // Sums over won games
+    pub total_time_ms: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub total_three_bv: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub total_clicks: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub best_three_bv_per_second: f64,
+}
+
+impl DifficultyStatistics {
+    pub fn record(&mut self, won: bool, metrics: &GameMetrics) {
+        self.played += 1;
+        if won {
+            let time_ms = metrics.time_ms;
+            self.won += 1;
+            self.current_streak += 1;
+            self.longest_streak = self.longest_streak.max(self.current_streak);
+            self.best_time_ms = Some(self.best_time_ms.map_or(time_ms, |best| best.min(time_ms)));
+            self.total_time_ms += time_ms;
+            self.total_three_bv += metrics.three_bv as u64;
+            self.total_clicks += metrics.clicks() as u64;
+            self.best_three_bv_per_second = self
+                .best_three_bv_per_second
+                .max(metrics.three_bv_per_second());
+        } else {
+            self.current_streak = 0;
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.won as f64 / self.played as f64
+        }
+    }
+
+    pub fn best_time(&self) -> Option<Duration> {
+        self.best_time_ms.map(Duration::from_millis)
+    }
+
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.won > 0).then(|| Duration::from_millis(self.total_time_ms / self.won as u64))
+    }
+
+    pub fn average_ioe(&self) -> Option<f64> {
+        (self.total_clicks > 0).then(|| self.total_three_bv as f64 / self.total_clicks as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistics {
+    pub format_version: u32,
+    // Keyed by config_name
+    pub difficulties: BTreeMap<String, DifficultyStatistics>,
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            difficulties: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl super::storage::Versioned for Statistics {
+    fn format_version_mut(&mut self) -> &mut u32 {
+        &mut self.format_version
+    }
+}
+
+impl Statistics {
+    pub fn record(&mut self, game_config: &GameConfig, won: bool, metrics: &GameMetrics) {
+        self.difficulties
+            .entry(config_name(game_config))
+            .or_default()
+            .record(won, metrics);
+    }
+
+    pub fn get(&self, game_config: &GameConfig) -> Option<&DifficultyStatistics> {
+        self.difficulties.get(&config_name(game_config))
+    }
+
+    pub fn reset(&mut self) {
+        self.difficulties.clear();
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn load() -> Self {
+        super::storage::load_json(STATISTICS_FILE)
+    }
+
+    #[cfg(feature = "persistence")]
+    pub fn save(&self) -> std::io::Result<()> {
+        super::storage::save_json(STATISTICS_FILE, self)
+    }
+
+    // Rows for a table, the difficulties first then custom boards
+    pub fn create_model(&self) -> ModelRc<ModelRc<StandardListViewItem>> {
+        let difficulties = GameDifficulty::keys();
+        let customs = self
+            .difficulties
+            .keys()
+            .filter(|name| !difficulties.contains(name))
+            .cloned();
+        let mut rows = Vec::new();
+        for name in difficulties.iter().cloned().chain(customs) {
+            let entry = self.difficulties.get(&name).cloned().unwrap_or_default();
+            let cells: Vec<StandardListViewItem> = [
+                name,
+                entry.played.to_string(),
+                entry.won.to_string(),
+                format!("{:.1}%", entry.win_rate() * 100.0),
+                entry.current_streak.to_string(),
+                entry.longest_streak.to_string(),
+                entry.best_time().map_or("-".into(), format_time),
+                entry.average_time().map_or("-".into(), format_time),
+                if entry.won > 0 {
+                    format!("{:.2}", entry.best_three_bv_per_second)
+                } else {
+                    "-".into()
+                },
+                entry
+                    .average_ioe()
+                    .map_or("-".into(), |ioe| format!("{:.2}", ioe)),
+            ]
+            .into_iter()
+            .map(|cell| cell.as_str().into())
+            .collect();
+            rows.push(VecModel::from_slice(&cells));
+        }
+        VecModel::from_slice(&rows)
+    }
+}
+
+// Name of a difficulty, or a description of a custom board, followed by the topology if not square
+// whether the edges wrap, the layers, the neighbourhood, the mines a tile can hold, the shape
+// whether the numbers lie and the lives
+pub fn config_name(game_config: &GameConfig) -> String {
+    let name = match GameDifficulty::from_config(game_config) {
+        Some(difficulty) => format!("{:?}", difficulty),
+        None => format!(
+            "Custom {}x{}, {} mines",
+            game_config.row_count, game_config.col_count, game_config.mine_count
+        ),
+    };
+    let name = match game_config.topology {
+        Topology::Square => name,
+        topology => format!("{name}, {topology:?}"),
+    };
+    let name = if game_config.wrap {
+        format!("{name}, wrapped")
+    } else {
+        name
+    };
+    let name = if game_config.layer_count > 1 {
+        format!("{name}, {} layers", game_config.layer_count)
+    } else {
+        name
+    };
+    let name = match game_config.neighbourhood {
+        Neighbourhood::Classic => name,
+        neighbourhood => format!("{name}, {neighbourhood:?} neighbours"),
+    };
+    let name = if game_config.max_tile_mines > 1 {
+        format!("{name}, up to {} mines per tile", game_config.max_tile_mines)
+    } else {
+        name
+    };
+    let name = match &game_config.mask {
+        Some(mask) => format!("{name}, {} shape", mask.name),
+        None => name,
+    };
+    let name = if game_config.liar {
+        format!("{name}, lying numbers")
+    } else {
+        name
+    };
+    let name = if game_config.lives > 1 {
+        format!("{name}, {} lives", game_config.lives)
+    } else {
+        name
+    };
+    // Keyed on the exact time limit, so limits within the same minute don't share statistics
+    match game_config.mode {
+        GameMode::Classic => name,
+        GameMode::Endless => format!("{name}, Endless"),
+        mode => format!("{name}, {mode:?} {} s", game_config.time_limit_secs),
+    }
+}
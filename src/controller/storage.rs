@@ -0,0 +1,135 @@
+// Files of the game kept in the user data directory as json
+// and settings kept in the user config directory as toml
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::FORMAT_VERSION;
+
+// Files carrying the format version they were written with
+pub trait Versioned {
+    fn format_version_mut(&mut self) -> &mut u32;
+
+    // Checks of the content beyond the version
+    fn is_valid(&self) -> bool {
+        true
+    }
+}
+
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(file_name))
+}
+
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(file_name))
+}
+
+pub fn load_json<T: DeserializeOwned + Default + Versioned>(file_name: &str) -> T {
+    match data_path(file_name) {
+        Some(path) => load_file(&path, |text| serde_json::from_str(text).ok()),
+        None => T::default(),
+    }
+}
+
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let path = data_path(file_name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    write_file(path, &serde_json::to_string_pretty(value)?)
+}
+
+pub fn load_toml<T: DeserializeOwned + Default + Versioned>(file_name: &str) -> T {
+    match config_path(file_name) {
+        Some(path) => load_file(&path, |text| toml::from_str(text).ok()),
+        None => T::default(),
+    }
+}
+
+pub fn save_toml<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let path = config_path(file_name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    let text = toml::to_string_pretty(value).map_err(io::Error::other)?;
+    write_file(path, &text)
+}
+
+fn write_file(path: PathBuf, text: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+}
+
+// Missing files are treated as not saved yet. Older files fill what they lack with defaults and are
+// saved again as the current version. Files that can not be parsed, are of a newer version or fail
+// their checks are kept as a .bak backup instead of being overwritten by the defaults
+fn load_file<T: Default + Versioned>(path: &Path, parse: impl FnOnce(&str) -> Option<T>) -> T {
+    let Ok(text) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    if let Some(mut value) = parse(&text) {
+        let format_version = value.format_version_mut();
+        if *format_version <= FORMAT_VERSION {
+            *format_version = FORMAT_VERSION;
+            if value.is_valid() {
+                return value;
+            }
+        }
+    }
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let _ = fs::rename(path, backup);
+    T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, serde::Deserialize)]
+    struct File {
+        format_version: u32,
+        value: u32,
+    }
+
+    impl Versioned for File {
+        fn format_version_mut(&mut self) -> &mut u32 {
+            &mut self.format_version
+        }
+    }
+
+    fn load(name: &str, text: &str) -> (File, bool) {
+        let dir = std::env::temp_dir().join(format!("{}-{}", env!("CARGO_PKG_NAME"), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("file.json");
+        let backup = dir.join("file.json.bak");
+        let _ = fs::remove_file(&backup);
+        fs::write(&path, text).unwrap();
+        let file = load_file(&path, |text| serde_json::from_str(text).ok());
+        let backed_up = backup.exists() && !path.exists();
+        fs::remove_dir_all(&dir).unwrap();
+        (file, backed_up)
+    }
+
+    #[test]
+    fn older_files_are_read_as_the_current_version() {
+        let text = r#"{"format_version": 0, "value": 7}"#;
+        let file = File {
+            format_version: FORMAT_VERSION,
+            value: 7,
+        };
+        assert_eq!(load("older", text), (file, false));
+    }
+
+    #[test]
+    fn newer_and_corrupt_files_are_backed_up() {
+        let text = format!(
+            r#"{{"format_version": {}, "value": 7}}"#,
+            FORMAT_VERSION + 1
+        );
+        assert_eq!(load("newer", &text), (File::default(), true));
+        assert_eq!(load("corrupt", "{"), (File::default(), true));
+    }
+}